@@ -1,89 +1,105 @@
 use std::env;
 use std::fs;
+use std::sync::OnceLock;
+
+use reorganise::reorganise_all;
+use rucksack::{shared_across, ItemAlphabet, Rucksack};
+
+mod reorganise;
+mod rucksack;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let file_path = args[1].clone();
     let contents = fs::read_to_string(file_path).expect("Should have been able to read the file");
-    let first_problem_contents = contents.clone();
-    let duplicates = first_problem_contents.split('\n').map(|rucksack| find_duplicate(rucksack));
-    let duplicate_values = duplicates.map(|d| get_value(d.unwrap()));
+    let rucksacks = contents.split('\n').filter(|line| !line.is_empty()).collect::<Vec<&str>>();
+
+    // day_03 <input> --reorganise [output]
+    let reorganising = args.get(2).is_some_and(|a| a == "--reorganise");
+    if reorganising && args.get(3).is_none() {
+        // only the rewritten input goes to stdout, so it can be piped straight back in
+        print_reorganisation(&rucksacks, None);
+        return;
+    }
 
-    let second_problem_contents = contents.clone();
-    let rucksacks = second_problem_contents.split('\n').collect::<Vec<&str>>();
+    let duplicate_values = rucksacks.iter().map(|rucksack| find_duplicates(rucksack).into_iter().map(get_value).sum::<u32>());
 
     println!("Problem 1: {}", duplicate_values.sum::<u32>());
-    println!("Problem 2: {}", find_all_badges(rucksacks, 3));
+    println!("Problem 2: {}", find_all_badges(rucksacks.clone(), 3));
+
+    if reorganising {
+        print_reorganisation(&rucksacks, args.get(3));
+    }
 }
 
+fn print_reorganisation(rucksacks: &[&str], output_path: Option<&String>) {
+    let reorganisation = match reorganise_all(standard_alphabet(), rucksacks) {
+        Ok(r) => r,
+        Err(e) => panic!("Could not reorganise: {}", e)
+    };
 
-fn find_duplicate(rucksack: &str) -> Option<char> {
-    if rucksack.len()%2 == 1 {
-        panic!("uneven rucksack");
+    // the report goes to stderr when stdout carries the rewritten input
+    let report = |line: String| if output_path.is_some() { println!("{}", line) } else { eprintln!("{}", line) };
+    for swap in reorganisation.swaps.iter() {
+        report(swap.to_string());
     }
+    report(format!("Swaps: {}", reorganisation.swaps.len()));
 
-    let pouch1 = rucksack.chars().take(rucksack.len() / 2);
-    let pouch2 = rucksack.chars().rev().take(rucksack.len() / 2);
-
-    let mut pouch1 = pouch1.collect::<Vec<char>>();
-    pouch1.sort();
-    let mut pouch2 = pouch2.collect::<Vec<char>>();
-    pouch2.sort();
-
-    // could be made faster... O(N^2)
-    for i in 0..pouch1.len() {
-        for j in 0..pouch2.len() {
-            if pouch1[i] == pouch2[j] {
-                return Some(pouch1[i]);
-            }
-        }
+    if let Err(e) = reorganisation.check(standard_alphabet(), rucksacks, 3) {
+        panic!("Reorganising broke the input: {}", e);
     }
+    report("Group badges unchanged".to_string());
 
-    println!("No duplicates found: {}", rucksack);
+    match output_path {
+        Some(path) => fs::write(path, reorganisation.to_input()).expect("Should have been able to write the file"),
+        None => println!("{}", reorganisation.to_input())
+    }
+}
 
-    return None;
+fn standard_alphabet() -> &'static ItemAlphabet {
+    static STANDARD: OnceLock<ItemAlphabet> = OnceLock::new();
+    STANDARD.get_or_init(ItemAlphabet::standard)
 }
 
-fn find_all_badges(rucksacks: Vec<&str>, group_size: usize) -> u32 {
-    if rucksacks.len() % group_size != 0 {
-        panic!("Invalid rucksack group");
+fn parse_rucksack(alphabet: &ItemAlphabet, rucksack: &str, compartments: usize) -> Rucksack {
+    match Rucksack::parse(alphabet, rucksack, compartments) {
+        Ok(parsed) => parsed,
+        Err(e) => panic!("Invalid rucksack {}: {}", rucksack, e)
     }
+}
 
-    let mut badge_values = 0;
-    for i in (0..rucksacks.len()).step_by(group_size) {
-        badge_values += find_badge(&rucksacks[i..i+group_size]);
-    }
+fn find_duplicates(rucksack: &str) -> Vec<char> {
+    let alphabet = standard_alphabet();
+    let rucksack = parse_rucksack(alphabet, rucksack, 2);
+    rucksack.shared_between_compartments().items(alphabet).collect()
+}
 
-    return badge_values;
+fn find_all_badges(rucksacks: Vec<&str>, group_size: usize) -> u32 {
+    find_group_badges(&rucksacks, group_size).iter().flatten().map(|b| get_value(*b)).sum()
 }
 
-fn find_badge(rucksack_group: &[&str]) -> u32 {
-    let mut group_detection = vec![[false; 52]; rucksack_group.len()];
-    for (i, group) in rucksack_group.iter().enumerate() {
-        for item in group.chars() {
-            let val = get_value(item);
-            group_detection[i][val as usize - 1] = true;
-        }
+fn find_group_badges(rucksacks: &[&str], group_size: usize) -> Vec<Vec<char>> {
+    if !rucksacks.len().is_multiple_of(group_size) {
+        panic!("Invalid rucksack group");
     }
 
-    for i in 0..52 {
-        let mut all_good = true;
-        for x in 0..rucksack_group.len() {
-            all_good &= group_detection[x][i];
-        }
+    let alphabet = standard_alphabet();
+    let parsed = rucksacks.iter().map(|r| parse_rucksack(alphabet, r, 1)).collect::<Vec<Rucksack>>();
+
+    parsed.chunks(group_size).map(find_badges).collect()
+}
 
-        if all_good {
-            return (i + 1).try_into().unwrap();
-        }
+fn find_badges(rucksack_group: &[Rucksack]) -> Vec<char> {
+    let badges = shared_across(rucksack_group).unwrap();
+    if badges.is_empty() {
+        panic!("Did not find a pair");
     }
 
-    panic!("Did not find a pair");
+    badges.items(standard_alphabet()).collect()
 }
 
 fn get_value(item: char) -> u32 {
-    let raw_ascii_val = item as u32;
-    if raw_ascii_val > 96 { return raw_ascii_val - 97 + 1 };
-    return raw_ascii_val - 65 + 26 + 1;
+    standard_alphabet().priority(item).unwrap_or_else(|| panic!("Unknown item: {}", item))
 }
 
 #[cfg(test)]
@@ -91,11 +107,12 @@ mod tests {
     use super::*;
     #[test]
     fn finds_duplicates() {
-        assert_eq!(None, find_duplicate("abcdef"));
-        assert_eq!(Some('c'), find_duplicate("abcdec"));
-        assert_eq!(Some('a'), find_duplicate("aaadea"));
-        assert_eq!(Some('p'), find_duplicate("vJrwpWtwJgWrhcsFMMfFFhFp"));
-        assert_eq!(Some('L'), find_duplicate("jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL"));
+        assert_eq!(Vec::<char>::new(), find_duplicates("abcdef"));
+        assert_eq!(vec!['c'], find_duplicates("abcdec"));
+        assert_eq!(vec!['a'], find_duplicates("aaadea"));
+        assert_eq!(vec!['p'], find_duplicates("vJrwpWtwJgWrhcsFMMfFFhFp"));
+        assert_eq!(vec!['L'], find_duplicates("jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL"));
+        assert_eq!(vec!['a', 'b'], find_duplicates("abXbaY"));
     }
 
     #[test]
@@ -0,0 +1,268 @@
+use crate::rucksack::{shared_across, ItemAlphabet, Rucksack, RucksackError};
+use std::fmt;
+
+// Swaps the item at `left_position` in the first compartment with the item at
+// `right_position` in the second. Positions are indices into the whole line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Swap {
+	pub rucksack: usize,
+	pub left_position: usize,
+	pub left_item: char,
+	pub right_position: usize,
+	pub right_item: char
+}
+
+impl fmt::Display for Swap {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "rucksack {}: swap '{}' at {} with '{}' at {}", self.rucksack + 1, self.left_item, self.left_position, self.right_item, self.right_position)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReorganiseError {
+	InvalidRucksack { rucksack: usize, error: RucksackError },
+	Unsplittable { rucksack: usize }
+}
+
+impl fmt::Display for ReorganiseError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ReorganiseError::InvalidRucksack { rucksack, error } => write!(f, "rucksack {}: {}", rucksack + 1, error),
+			ReorganiseError::Unsplittable { rucksack } => write!(f, "rucksack {}: items cannot be split so no type is in both compartments", rucksack + 1)
+		}
+	}
+}
+
+#[derive(Debug)]
+pub struct Reorganisation {
+	pub rucksacks: Vec<String>,
+	pub swaps: Vec<Swap>
+}
+
+impl Reorganisation {
+	pub fn to_input(&self) -> String {
+		self.rucksacks.join("\n")
+	}
+
+	// Checks the repacked rucksacks against the ones they came from: each must
+	// hold the same items, and every group must still share exactly one badge,
+	// the same one as before.
+	pub fn check(&self, alphabet: &ItemAlphabet, original: &[&str], group_size: usize) -> Result<(), CheckError> {
+		if self.rucksacks.len() != original.len() {
+			return Err(CheckError::RucksackCount { expected: original.len(), found: self.rucksacks.len() });
+		}
+
+		for (rucksack, (before, after)) in original.iter().zip(self.rucksacks.iter()).enumerate() {
+			if sorted_items(before) != sorted_items(after) {
+				return Err(CheckError::ItemsChanged { rucksack });
+			}
+		}
+
+		let repacked = self.rucksacks.iter().map(|r| r.as_str()).collect::<Vec<&str>>();
+		for (group, (before, after)) in original.chunks(group_size).zip(repacked.chunks(group_size)).enumerate() {
+			let badges = group_badges(alphabet, after).ok_or(CheckError::BadgesChanged { group, badges: vec![] })?;
+			if badges.len() != 1 || group_badges(alphabet, before).as_ref() != Some(&badges) {
+				return Err(CheckError::BadgesChanged { group, badges });
+			}
+		}
+
+		Ok(())
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckError {
+	RucksackCount { expected: usize, found: usize },
+	ItemsChanged { rucksack: usize },
+	BadgesChanged { group: usize, badges: Vec<char> }
+}
+
+impl fmt::Display for CheckError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			CheckError::RucksackCount { expected, found } => write!(f, "expected {} rucksacks but found {}", expected, found),
+			CheckError::ItemsChanged { rucksack } => write!(f, "rucksack {} no longer holds the same items", rucksack + 1),
+			CheckError::BadgesChanged { group, badges } => write!(f, "group {} now shares {:?} instead of its one badge", group + 1, badges)
+		}
+	}
+}
+
+fn sorted_items(rucksack: &str) -> Vec<char> {
+	let mut items = rucksack.chars().collect::<Vec<char>>();
+	items.sort_unstable();
+	items
+}
+
+// Every item the group carries in common, or None if a rucksack does not parse.
+fn group_badges(alphabet: &ItemAlphabet, group: &[&str]) -> Option<Vec<char>> {
+	let parsed = group.iter().map(|r| Rucksack::parse(alphabet, r, 1).ok()).collect::<Option<Vec<Rucksack>>>()?;
+	Some(shared_across(&parsed)?.items(alphabet).collect())
+}
+
+pub fn reorganise_all(alphabet: &ItemAlphabet, rucksacks: &[&str]) -> Result<Reorganisation, ReorganiseError> {
+	let mut reorganisation = Reorganisation { rucksacks: vec![], swaps: vec![] };
+	for (index, rucksack) in rucksacks.iter().enumerate() {
+		let (packed, swaps) = reorganise(alphabet, index, rucksack)?;
+		reorganisation.rucksacks.push(packed);
+		reorganisation.swaps.extend(swaps);
+	}
+
+	Ok(reorganisation)
+}
+
+// Repacks one rucksack with the fewest swaps so that every item type ends up in
+// a single compartment. Each swap moves one item each way, so the compartment
+// sizes never change; choosing which side each type lives on is a knapsack
+// over the item types where the first compartment must be filled exactly.
+pub fn reorganise(alphabet: &ItemAlphabet, rucksack_index: usize, rucksack: &str) -> Result<(String, Vec<Swap>), ReorganiseError> {
+	let mut items = rucksack.chars().collect::<Vec<char>>();
+	if !items.len().is_multiple_of(2) {
+		return Err(ReorganiseError::InvalidRucksack { rucksack: rucksack_index, error: RucksackError::UnevenCompartments { length: items.len(), compartments: 2 } });
+	}
+
+	let half = items.len() / 2;
+	let mut left_counts = vec![0; alphabet.item_count()];
+	let mut right_counts = vec![0; alphabet.item_count()];
+	let mut indices = vec![];
+	for (position, item) in items.iter().enumerate() {
+		let index = alphabet.index_of(*item).ok_or(ReorganiseError::InvalidRucksack { rucksack: rucksack_index, error: RucksackError::UnknownItem { item: *item, position } })?;
+		if position < half {
+			left_counts[index] += 1;
+		} else {
+			right_counts[index] += 1;
+		}
+		indices.push(index);
+	}
+
+	let goes_left = choose_sides(&left_counts, &right_counts, half).ok_or(ReorganiseError::Unsplittable { rucksack: rucksack_index })?;
+
+	let leaving_left = (0..half).filter(|p| !goes_left[indices[*p]]);
+	let leaving_right = (half..items.len()).filter(|p| goes_left[indices[*p]]);
+	let mut swaps = vec![];
+	for (left_position, right_position) in leaving_left.zip(leaving_right) {
+		swaps.push(Swap {
+			rucksack: rucksack_index,
+			left_position,
+			left_item: items[left_position],
+			right_position,
+			right_item: items[right_position]
+		});
+		items.swap(left_position, right_position);
+	}
+
+	Ok((items.into_iter().collect(), swaps))
+}
+
+// For every item type, decides whether it belongs in the first compartment.
+// The cost of a choice is the number of items that have to cross over.
+fn choose_sides(left_counts: &[usize], right_counts: &[usize], half: usize) -> Option<Vec<bool>> {
+	let types = left_counts.len();
+	// best[t][s]: fewest crossings for the first t types with s items placed left,
+	// and whether type t - 1 was placed left to get there
+	let mut best: Vec<Vec<Option<(usize, bool)>>> = vec![vec![None; half + 1]; types + 1];
+	best[0][0] = Some((0, false));
+	for t in 0..types {
+		let total = left_counts[t] + right_counts[t];
+		for filled in 0..=half {
+			let Some((cost, _)) = best[t][filled] else { continue };
+
+			let right_cost = cost + left_counts[t];
+			if best[t + 1][filled].is_none_or(|(c, _)| right_cost < c) {
+				best[t + 1][filled] = Some((right_cost, false));
+			}
+
+			let left_cost = cost + right_counts[t];
+			if filled + total <= half && best[t + 1][filled + total].is_none_or(|(c, _)| left_cost < c) {
+				best[t + 1][filled + total] = Some((left_cost, true));
+			}
+		}
+	}
+
+	best[types][half]?;
+
+	let mut goes_left = vec![false; types];
+	let mut filled = half;
+	for t in (0..types).rev() {
+		let (_, left) = best[t + 1][filled].unwrap();
+		if left {
+			goes_left[t] = true;
+			filled -= left_counts[t] + right_counts[t];
+		}
+	}
+
+	Some(goes_left)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn has_no_shared_items(rucksack: &str) -> bool {
+		let (left, right) = rucksack.split_at(rucksack.len() / 2);
+		!left.chars().any(|c| right.contains(c))
+	}
+
+	#[test]
+	fn untouched_when_already_valid() {
+		let alphabet = ItemAlphabet::standard();
+		let (packed, swaps) = reorganise(&alphabet, 0, "abcdef").unwrap();
+		assert_eq!("abcdef", packed);
+		assert!(swaps.is_empty());
+	}
+
+	#[test]
+	fn single_swap() {
+		let alphabet = ItemAlphabet::standard();
+		let (packed, swaps) = reorganise(&alphabet, 0, "abcdec").unwrap();
+		assert!(has_no_shared_items(&packed));
+		assert_eq!(1, swaps.len());
+	}
+
+	#[test]
+	fn fewest_swaps() {
+		let alphabet = ItemAlphabet::standard();
+		// trading the left 'a' for the 'd' beats moving all three 'b's across
+		let (packed, swaps) = reorganise(&alphabet, 0, "abbbcacd").unwrap();
+		assert_eq!("dbbbcaca", packed);
+		assert_eq!(vec![Swap { rucksack: 0, left_position: 0, left_item: 'a', right_position: 7, right_item: 'd' }], swaps);
+	}
+
+	#[test]
+	fn example_rucksacks() {
+		let alphabet = ItemAlphabet::standard();
+		let input = ["vJrwpWtwJgWrhcsFMMfFFhFp", "jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL", "PmmdzqPrVvPwwTWBwg"];
+		let reorganisation = reorganise_all(&alphabet, &input).unwrap();
+		assert!(reorganisation.rucksacks.iter().all(|r| has_no_shared_items(r)));
+		for (before, after) in input.iter().zip(reorganisation.rucksacks.iter()) {
+			let mut before = before.chars().collect::<Vec<char>>();
+			let mut after = after.chars().collect::<Vec<char>>();
+			before.sort();
+			after.sort();
+			assert_eq!(before, after);
+		}
+	}
+
+	#[test]
+	fn check_compares_with_the_original() {
+		let alphabet = ItemAlphabet::standard();
+		let input = ["vJrwpWtwJgWrhcsFMMfFFhFp", "jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL", "PmmdzqPrVvPwwTWBwg"];
+		let reorganisation = reorganise_all(&alphabet, &input).unwrap();
+		assert_eq!(Ok(()), reorganisation.check(&alphabet, &input, 3));
+
+		let mut changed = reorganisation.rucksacks.clone();
+		changed[1] = changed[1].replacen('L', "l", 1);
+		let tampered = Reorganisation { rucksacks: changed, swaps: vec![] };
+		assert_eq!(Err(CheckError::ItemsChanged { rucksack: 1 }), tampered.check(&alphabet, &input, 3));
+
+		// a group sharing two items has no single badge to keep
+		let shared = ["ar", "ar", "ar"];
+		let reorganisation = reorganise_all(&alphabet, &shared).unwrap();
+		assert_eq!(Err(CheckError::BadgesChanged { group: 0, badges: vec!['a', 'r'] }), reorganisation.check(&alphabet, &shared, 3));
+	}
+
+	#[test]
+	fn unsplittable() {
+		let alphabet = ItemAlphabet::standard();
+		assert_eq!(Some(ReorganiseError::Unsplittable { rucksack: 2 }), reorganise(&alphabet, 2, "aaab").err());
+	}
+}
@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+use std::fmt;
+
+const WORD_BITS: usize = 64;
+
+// Maps item characters to dense indices. An item's priority is its index + 1,
+// so the standard alphabet reproduces the puzzle's a..z = 1..26, A..Z = 27..52.
+#[derive(Debug, Clone)]
+pub struct ItemAlphabet {
+	items: Vec<char>,
+	ascii_lookup: [Option<usize>; 128],
+	other_lookup: HashMap<char, usize>
+}
+
+impl ItemAlphabet {
+	pub fn new(items: &str) -> ItemAlphabet {
+		let mut alphabet = ItemAlphabet {
+			items: vec![],
+			ascii_lookup: [None; 128],
+			other_lookup: HashMap::new()
+		};
+
+		for item in items.chars() {
+			if alphabet.index_of(item).is_some() {
+				panic!("Duplicate item in alphabet: {}", item);
+			}
+
+			let index = alphabet.items.len();
+			if item.is_ascii() {
+				alphabet.ascii_lookup[item as usize] = Some(index);
+			} else {
+				alphabet.other_lookup.insert(item, index);
+			}
+			alphabet.items.push(item);
+		}
+
+		alphabet
+	}
+
+	pub fn standard() -> ItemAlphabet {
+		ItemAlphabet::new("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ")
+	}
+
+	pub fn item_count(&self) -> usize {
+		self.items.len()
+	}
+
+	pub fn index_of(&self, item: char) -> Option<usize> {
+		if item.is_ascii() {
+			return self.ascii_lookup[item as usize];
+		}
+
+		self.other_lookup.get(&item).copied()
+	}
+
+	pub fn item(&self, index: usize) -> char {
+		self.items[index]
+	}
+
+	pub fn priority(&self, item: char) -> Option<u32> {
+		self.index_of(item).map(|index| index as u32 + 1)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemSet {
+	words: Vec<u64>
+}
+
+impl ItemSet {
+	pub fn empty(alphabet_len: usize) -> ItemSet {
+		ItemSet {
+			words: vec![0; alphabet_len.div_ceil(WORD_BITS)]
+		}
+	}
+
+	pub fn insert(&mut self, index: usize) {
+		self.words[index / WORD_BITS] |= 1 << (index % WORD_BITS);
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.words.iter().all(|w| *w == 0)
+	}
+
+	pub fn intersect_with(&mut self, other: &ItemSet) {
+		for (word, other_word) in self.words.iter_mut().zip(other.words.iter()) {
+			*word &= other_word;
+		}
+	}
+
+	// Yields the alphabet indices of every item in the set, lowest first.
+	pub fn indices(&self) -> impl Iterator<Item = usize> + '_ {
+		self.words.iter().enumerate().flat_map(|(word_idx, word)| {
+			let mut remaining = *word;
+			std::iter::from_fn(move || {
+				if remaining == 0 {
+					return None;
+				}
+				let bit = remaining.trailing_zeros() as usize;
+				remaining &= remaining - 1;
+				Some(word_idx * WORD_BITS + bit)
+			})
+		})
+	}
+
+	pub fn items<'a>(&'a self, alphabet: &'a ItemAlphabet) -> impl Iterator<Item = char> + 'a {
+		self.indices().map(|index| alphabet.item(index))
+	}
+
+	// Intersection of every set yielded, or None when there are no sets at all.
+	pub fn intersection<'a>(sets: impl IntoIterator<Item = &'a ItemSet>) -> Option<ItemSet> {
+		let mut sets = sets.into_iter();
+		let mut shared = sets.next()?.clone();
+		for set in sets {
+			shared.intersect_with(set);
+		}
+
+		Some(shared)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RucksackError {
+	UnknownItem { item: char, position: usize },
+	UnevenCompartments { length: usize, compartments: usize },
+	NoCompartments
+}
+
+impl fmt::Display for RucksackError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			RucksackError::UnknownItem { item, position } => write!(f, "unknown item '{}' at position {}", item, position),
+			RucksackError::UnevenCompartments { length, compartments } => write!(f, "{} items cannot be split into {} equal compartments", length, compartments),
+			RucksackError::NoCompartments => write!(f, "a rucksack needs at least one compartment")
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct Rucksack {
+	compartments: Vec<ItemSet>,
+	contents: ItemSet
+}
+
+impl Rucksack {
+	pub fn parse(alphabet: &ItemAlphabet, line: &str, compartment_count: usize) -> Result<Rucksack, RucksackError> {
+		if compartment_count == 0 {
+			return Err(RucksackError::NoCompartments);
+		}
+
+		let items = line.chars().collect::<Vec<char>>();
+		if !items.len().is_multiple_of(compartment_count) {
+			return Err(RucksackError::UnevenCompartments { length: items.len(), compartments: compartment_count });
+		}
+
+		let compartment_size = items.len() / compartment_count;
+		let mut compartments = vec![ItemSet::empty(alphabet.item_count()); compartment_count];
+		let mut contents = ItemSet::empty(alphabet.item_count());
+		for (position, item) in items.iter().enumerate() {
+			let index = alphabet.index_of(*item).ok_or(RucksackError::UnknownItem { item: *item, position })?;
+			compartments[position / compartment_size].insert(index);
+			contents.insert(index);
+		}
+
+		Ok(Rucksack { compartments, contents })
+	}
+
+	pub fn contents(&self) -> &ItemSet {
+		&self.contents
+	}
+
+	// Items that appear in every compartment of this rucksack.
+	pub fn shared_between_compartments(&self) -> ItemSet {
+		ItemSet::intersection(&self.compartments).unwrap()
+	}
+}
+
+// Items carried by every rucksack in the group.
+pub fn shared_across(rucksacks: &[Rucksack]) -> Option<ItemSet> {
+	ItemSet::intersection(rucksacks.iter().map(|r| r.contents()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn standard_alphabet_priorities() {
+		let alphabet = ItemAlphabet::standard();
+		assert_eq!(Some(1), alphabet.priority('a'));
+		assert_eq!(Some(27), alphabet.priority('A'));
+		assert_eq!(Some(52), alphabet.priority('Z'));
+		assert_eq!(None, alphabet.priority('1'));
+	}
+
+	#[test]
+	fn custom_alphabet_beyond_one_word() {
+		let items = (0..200u32).map(|i| char::from_u32(0x100 + i).unwrap()).collect::<Vec<char>>();
+		let alphabet = ItemAlphabet::new(&items.iter().collect::<String>());
+		assert_eq!(Some(200), alphabet.priority(items[199]));
+
+		let line = [items[0], items[199], items[5], items[199]].iter().collect::<String>();
+		let rucksack = Rucksack::parse(&alphabet, &line, 2).unwrap();
+		assert_eq!(vec![items[199]], rucksack.shared_between_compartments().items(&alphabet).collect::<Vec<char>>());
+	}
+
+	#[test]
+	fn reports_every_shared_item() {
+		let alphabet = ItemAlphabet::standard();
+		let rucksack = Rucksack::parse(&alphabet, "abcXcbaY", 2).unwrap();
+		assert_eq!(vec!['a', 'b', 'c'], rucksack.shared_between_compartments().items(&alphabet).collect::<Vec<char>>());
+	}
+
+	#[test]
+	fn intersects_any_number_of_compartments() {
+		let alphabet = ItemAlphabet::standard();
+		let rucksack = Rucksack::parse(&alphabet, "abcbcdcde", 3).unwrap();
+		assert_eq!(vec!['c'], rucksack.shared_between_compartments().items(&alphabet).collect::<Vec<char>>());
+	}
+
+	#[test]
+	fn intersects_groups() {
+		let alphabet = ItemAlphabet::standard();
+		let group = ["vJrwpWtwJgWrhcsFMMfFFhFp", "jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL", "PmmdzqPrVvPwwTWBwg"]
+			.iter()
+			.map(|line| Rucksack::parse(&alphabet, line, 2).unwrap())
+			.collect::<Vec<Rucksack>>();
+		assert_eq!(vec!['r'], shared_across(&group).unwrap().items(&alphabet).collect::<Vec<char>>());
+		assert_eq!(None, shared_across(&[]));
+	}
+
+	#[test]
+	fn rejects_bad_rucksacks() {
+		let alphabet = ItemAlphabet::standard();
+		assert_eq!(Some(RucksackError::UnknownItem { item: '!', position: 1 }), Rucksack::parse(&alphabet, "a!", 2).err());
+		assert_eq!(Some(RucksackError::UnevenCompartments { length: 3, compartments: 2 }), Rucksack::parse(&alphabet, "abc", 2).err());
+		assert_eq!(Some(RucksackError::NoCompartments), Rucksack::parse(&alphabet, "ab", 0).err());
+	}
+}
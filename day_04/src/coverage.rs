@@ -0,0 +1,157 @@
+// Whole-camp analysis over every elf's section range. Elves are identified by
+// their position in the slice handed in, ranges are inclusive on both ends.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Segment {
+	pub start: u32,
+	pub end: u32,
+	pub elves: u32
+}
+
+// Every section from 1 (or 0, when an elf is assigned it) up to the highest
+// assigned one, split into runs that are covered by the same number of elves.
+#[derive(Debug)]
+pub struct Coverage {
+	segments: Vec<Segment>
+}
+
+impl Coverage {
+	// Sweeps the range boundaries once so every later query works on the
+	// compressed segments instead of individual sections. Boundaries are u64 so
+	// a range ending at u32::MAX still has a section after it.
+	pub fn from_assignments(assignments: &[(u32, u32)]) -> Coverage {
+		let mut events = vec![];
+		for (start, end) in assignments.iter() {
+			events.push((*start as u64, 1i64));
+			events.push((*end as u64 + 1, -1i64));
+		}
+		events.sort();
+
+		let mut segments = vec![];
+		let mut next_section = events.first().map_or(1, |(section, _)| (*section).min(1));
+		let mut elves = 0i64;
+		for (section, delta) in events {
+			if section > next_section {
+				segments.push(Segment { start: next_section as u32, end: (section - 1) as u32, elves: elves as u32 });
+				next_section = section;
+			}
+			elves += delta;
+		}
+
+		Coverage { segments }
+	}
+
+	pub fn uncovered(&self) -> Vec<(u32, u32)> {
+		self.segments.iter().filter(|s| s.elves == 0).map(|s| (s.start, s.end)).collect()
+	}
+
+	pub fn covered_by_more_than(&self, k: u32) -> Vec<Segment> {
+		self.segments.iter().filter(|s| s.elves > k).copied().collect()
+	}
+
+	fn fewest_elves_within(&self, start: u32, end: u32) -> u32 {
+		let first = self.segments.partition_point(|s| s.end < start);
+		self.segments[first..].iter().take_while(|s| s.start <= end).map(|s| s.elves).min().unwrap_or(0)
+	}
+}
+
+// Elves whose whole range is also covered by the union of everyone else.
+pub fn redundant_elves(assignments: &[(u32, u32)]) -> Vec<usize> {
+	let coverage = Coverage::from_assignments(assignments);
+	assignments.iter()
+		.enumerate()
+		.filter(|(_, (start, end))| coverage.fewest_elves_within(*start, *end) >= 2)
+		.map(|(elf, _)| elf)
+		.collect()
+}
+
+// Smallest set of elves whose ranges still cover every assigned section. The
+// greedy choice of the range reaching furthest from the first uncovered section
+// is optimal for intervals on a line.
+pub fn minimal_cover(assignments: &[(u32, u32)]) -> Vec<usize> {
+	let mut by_start = (0..assignments.len()).collect::<Vec<usize>>();
+	by_start.sort_by_key(|elf| assignments[*elf].0);
+
+	let mut chosen = vec![];
+	let mut next = 0;
+	// first section that still needs covering
+	let mut uncovered_from = 0;
+	while next < by_start.len() {
+		let (start, _) = assignments[by_start[next]];
+		uncovered_from = uncovered_from.max(start);
+
+		let mut best: Option<usize> = None;
+		while next < by_start.len() && assignments[by_start[next]].0 <= uncovered_from {
+			let elf = by_start[next];
+			if best.is_none_or(|b| assignments[elf].1 > assignments[b].1) {
+				best = Some(elf);
+			}
+			next += 1;
+		}
+
+		let best = best.unwrap();
+		if assignments[best].1 >= uncovered_from {
+			chosen.push(best);
+			// nothing is left to cover past the last section
+			let Some(after) = assignments[best].1.checked_add(1) else { break };
+			uncovered_from = after;
+		}
+	}
+
+	chosen.sort();
+	chosen
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use super::*;
+
+	// The puzzle's example pairs, one elf after the other.
+	pub(crate) const EXAMPLE: [(u32, u32); 12] = [(2, 4), (6, 8), (2, 3), (4, 5), (5, 7), (7, 9), (2, 8), (3, 7), (6, 6), (4, 6), (2, 6), (4, 8)];
+
+	#[test]
+	fn finds_uncovered_sections() {
+		let coverage = Coverage::from_assignments(&[(3, 4), (7, 9), (8, 8)]);
+		assert_eq!(vec![(1, 2), (5, 6)], coverage.uncovered());
+		assert_eq!(vec![Segment { start: 8, end: 8, elves: 2 }], coverage.covered_by_more_than(1));
+	}
+
+	#[test]
+	fn example_coverage() {
+		let coverage = Coverage::from_assignments(&EXAMPLE);
+		assert_eq!(vec![(1, 1)], coverage.uncovered());
+		assert_eq!(vec![Segment { start: 6, end: 6, elves: 8 }], coverage.covered_by_more_than(7));
+	}
+
+	#[test]
+	fn handles_the_ends_of_the_section_range() {
+		let coverage = Coverage::from_assignments(&[(1, u32::MAX), (5, 6)]);
+		assert_eq!(Vec::<(u32, u32)>::new(), coverage.uncovered());
+		assert_eq!(vec![Segment { start: 5, end: 6, elves: 2 }], coverage.covered_by_more_than(1));
+		assert_eq!(vec![0], minimal_cover(&[(1, u32::MAX), (5, 6)]));
+		assert_eq!(vec![0], minimal_cover(&[(1, u32::MAX), (u32::MAX, u32::MAX)]));
+		assert_eq!(vec![1], redundant_elves(&[(1, u32::MAX), (u32::MAX, u32::MAX)]));
+
+		let coverage = Coverage::from_assignments(&[(0, 0), (2, 3)]);
+		assert_eq!(vec![(1, 1)], coverage.uncovered());
+		assert_eq!(vec![Segment { start: 0, end: 0, elves: 1 }, Segment { start: 2, end: 3, elves: 1 }], coverage.covered_by_more_than(0));
+		assert_eq!(vec![0, 1], minimal_cover(&[(0, 0), (2, 3)]));
+	}
+
+	#[test]
+	fn finds_redundant_elves() {
+		assert_eq!(vec![1], redundant_elves(&[(1, 5), (2, 3), (6, 6)]));
+		// each range is covered by the other
+		assert_eq!(vec![0, 1], redundant_elves(&[(1, 3), (1, 3)]));
+		// the union of the neighbours covers the middle
+		assert_eq!(vec![1], redundant_elves(&[(1, 4), (3, 6), (5, 9)]));
+	}
+
+	#[test]
+	fn finds_minimal_cover() {
+		assert_eq!(vec![0, 2], minimal_cover(&[(1, 4), (3, 6), (5, 9)]));
+		assert_eq!(vec![1, 3], minimal_cover(&[(1, 2), (1, 5), (20, 21), (20, 30)]));
+		assert_eq!(vec![5, 6], minimal_cover(&EXAMPLE));
+		assert_eq!(Vec::<usize>::new(), minimal_cover(&[]));
+	}
+}
@@ -0,0 +1,152 @@
+// Static interval tree over the elves' section ranges. The ranges are sorted by
+// start and laid out as an implicit balanced tree (the middle of every slice is
+// its root), with each node remembering the furthest end in its subtree so whole
+// branches can be skipped. Queries cost O(log n + matches).
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+	start: u32,
+	end: u32,
+	elf: usize,
+	max_end: u32
+}
+
+#[derive(Debug)]
+pub struct AssignmentTree {
+	nodes: Vec<Node>,
+	assignments: Vec<(u32, u32)>
+}
+
+impl AssignmentTree {
+	// Elves are numbered by their position in `assignments`.
+	pub fn new(assignments: &[(u32, u32)]) -> AssignmentTree {
+		let mut nodes = assignments.iter()
+			.enumerate()
+			.map(|(elf, (start, end))| Node { start: *start, end: *end, elf, max_end: *end })
+			.collect::<Vec<Node>>();
+		nodes.sort_by_key(|n| (n.start, n.end, n.elf));
+
+		let len = nodes.len();
+		AssignmentTree::fill_max_end(&mut nodes, 0, len);
+
+		AssignmentTree { nodes, assignments: assignments.to_vec() }
+	}
+
+	fn fill_max_end(nodes: &mut [Node], low: usize, high: usize) -> u32 {
+		if low >= high {
+			return 0;
+		}
+
+		let mid = (low + high) / 2;
+		let left = AssignmentTree::fill_max_end(nodes, low, mid);
+		let right = AssignmentTree::fill_max_end(nodes, mid + 1, high);
+		nodes[mid].max_end = nodes[mid].end.max(left).max(right);
+		nodes[mid].max_end
+	}
+
+	fn collect_overlapping(&self, low: usize, high: usize, start: u32, end: u32, found: &mut Vec<usize>) {
+		if low >= high {
+			return;
+		}
+
+		let mid = (low + high) / 2;
+		if self.nodes[mid].max_end < start {
+			return;
+		}
+
+		self.collect_overlapping(low, mid, start, end, found);
+		let node = self.nodes[mid];
+		if node.start > end {
+			// everything to the right starts even later
+			return;
+		}
+		if node.end >= start {
+			found.push(node.elf);
+		}
+		self.collect_overlapping(mid + 1, high, start, end, found);
+	}
+
+	// Elves whose range shares at least one section with start..=end.
+	pub fn overlapping_range(&self, start: u32, end: u32) -> Vec<usize> {
+		let mut found = vec![];
+		self.collect_overlapping(0, self.nodes.len(), start, end, &mut found);
+		found.sort();
+		found
+	}
+
+	pub fn covering(&self, section: u32) -> Vec<usize> {
+		self.overlapping_range(section, section)
+	}
+
+	// Every other elf sharing a section with `elf`, or None when there is no
+	// such elf.
+	pub fn overlapping(&self, elf: usize) -> Option<Vec<usize>> {
+		let (start, end) = *self.assignments.get(elf)?;
+		Some(self.overlapping_range(start, end).into_iter().filter(|other| *other != elf).collect())
+	}
+
+	// Every pair of elves with at least one section in common, lower elf first.
+	pub fn overlapping_pairs(&self) -> Vec<(usize, usize)> {
+		let mut pairs = vec![];
+		for (elf, (start, end)) in self.assignments.iter().enumerate() {
+			let mut found = vec![];
+			self.collect_overlapping(0, self.nodes.len(), *start, *end, &mut found);
+			pairs.extend(found.into_iter().filter(|other| *other > elf).map(|other| (elf, other)));
+		}
+
+		pairs.sort();
+		pairs
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::coverage::tests::EXAMPLE;
+
+	fn brute_force_pairs(assignments: &[(u32, u32)]) -> Vec<(usize, usize)> {
+		let mut pairs = vec![];
+		for a in 0..assignments.len() {
+			for b in a + 1..assignments.len() {
+				if assignments[a].0 <= assignments[b].1 && assignments[b].0 <= assignments[a].1 {
+					pairs.push((a, b));
+				}
+			}
+		}
+
+		pairs
+	}
+
+	#[test]
+	fn finds_elves_covering_section() {
+		let tree = AssignmentTree::new(&EXAMPLE);
+		assert_eq!(vec![5], tree.covering(9));
+		assert_eq!(vec![0, 2, 6, 10], tree.covering(2));
+		assert_eq!(Vec::<usize>::new(), tree.covering(1));
+		assert_eq!(Vec::<usize>::new(), tree.covering(10));
+	}
+
+	#[test]
+	fn finds_elves_overlapping_elf() {
+		let tree = AssignmentTree::new(&EXAMPLE);
+		assert_eq!(Some(vec![1, 4, 6, 7, 11]), tree.overlapping(5));
+		assert_eq!(Some(vec![0, 6, 7, 10]), tree.overlapping(2));
+		assert_eq!(None, tree.overlapping(12));
+	}
+
+	#[test]
+	fn pairs_match_brute_force() {
+		let tree = AssignmentTree::new(&EXAMPLE);
+		assert_eq!(brute_force_pairs(&EXAMPLE), tree.overlapping_pairs());
+
+		let spread = (0..200u32).map(|i| ((i * 37) % 500, (i * 37) % 500 + i % 7)).collect::<Vec<(u32, u32)>>();
+		assert_eq!(brute_force_pairs(&spread), AssignmentTree::new(&spread).overlapping_pairs());
+	}
+
+	#[test]
+	fn empty_tree() {
+		let tree = AssignmentTree::new(&[]);
+		assert_eq!(Vec::<usize>::new(), tree.covering(1));
+		assert_eq!(Vec::<(usize, usize)>::new(), tree.overlapping_pairs());
+	}
+}
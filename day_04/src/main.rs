@@ -1,31 +1,161 @@
 use std::env;
+use std::fmt;
 use std::fs;
 
+use coverage::{minimal_cover, redundant_elves, Coverage};
+use interval_tree::AssignmentTree;
+
+mod coverage;
+mod interval_tree;
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let file_path = args[1].clone();
     let contents = fs::read_to_string(file_path).expect("Should have been able to read the file");
-    let first_problem_contents = contents.clone();
+    let groups = contents.split('\n')
+        .enumerate()
+        .filter(|(_, assignment)| !assignment.is_empty())
+        .map(|(line, assignment)| match parse_assignment_ranges(assignment) {
+            Ok(group) => (line + 1, group),
+            Err(e) => panic!("Line {}: {}", line + 1, e)
+        })
+        .collect::<Vec<(usize, Vec<(u32, u32)>)>>();
 
-    let assignments_do_overlap = first_problem_contents.split('\n')
-        .map(|assignment| parse_assignment_ranges(assignment))
-        .map(|(a1s, a1e, a2s, a2e)| does_assignment_pair_overlap(a1s, a1e, a2s, a2e))
-        .filter(|ado| *ado == true)
+    let assignments_do_overlap = groups.iter()
+        .filter(|(_, group)| does_assignment_group_overlap(group))
         .count();
 
-    let assignments_overlap_at_all = contents.split('\n')
-        .map(|assignment| parse_assignment_ranges(assignment))
-        .map(|(a1s, a1e, a2s, a2e)| does_assignment_pair_overlap_simple(a1s, a1e, a2s, a2e))
-        .filter(|ado| *ado == true)
+    let assignments_overlap_at_all = groups.iter()
+        .filter(|(_, group)| does_assignment_group_overlap_simple(group))
         .count();
 
     println!("Problem 1: {}", assignments_do_overlap);
     println!("Problem 2: {}", assignments_overlap_at_all);
+
+    let elves = Elves::from_groups(&groups);
+    let number_arg = || args.get(3).map(|n| n.parse::<u32>().expect("Should have been a number"));
+    match args.get(2).map(|a| a.as_str()) {
+        // day_04 <input> --coverage <k>
+        Some("--coverage") => print_coverage(&elves, number_arg().unwrap_or(1)),
+        // day_04 <input> --covering <section>
+        Some("--covering") => {
+            let tree = AssignmentTree::new(&elves.ranges);
+            for elf in tree.covering(number_arg().expect("Missing section")) {
+                println!("{}", elves.describe(elf));
+            }
+        },
+        // day_04 <input> --overlapping <elf>
+        Some("--overlapping") => {
+            let tree = AssignmentTree::new(&elves.ranges);
+            let elf = number_arg().expect("Missing elf") as usize;
+            let overlapping = tree.overlapping(elf).unwrap_or_else(|| panic!("There is no elf {}, only {}", elf, elves.ranges.len()));
+            for elf in overlapping {
+                println!("{}", elves.describe(elf));
+            }
+        },
+        // day_04 <input> --pairs
+        Some("--pairs") => {
+            let tree = AssignmentTree::new(&elves.ranges);
+            for (a, b) in tree.overlapping_pairs() {
+                println!("{} / {}", elves.describe(a), elves.describe(b));
+            }
+        },
+        _ => {}
+    }
+}
+
+// Every range in the file, numbered in reading order, along with its line.
+struct Elves {
+    ranges: Vec<(u32, u32)>,
+    lines: Vec<usize>
+}
+
+impl Elves {
+    fn from_groups(groups: &[(usize, Vec<(u32, u32)>)]) -> Elves {
+        let mut elves = Elves { ranges: vec![], lines: vec![] };
+        for (line, group) in groups.iter() {
+            for range in group.iter() {
+                elves.ranges.push(*range);
+                elves.lines.push(*line);
+            }
+        }
+
+        elves
+    }
+
+    fn describe(&self, elf: usize) -> String {
+        format!("elf {} (line {}, {}-{})", elf, self.lines[elf], self.ranges[elf].0, self.ranges[elf].1)
+    }
 }
 
-fn parse_assignment_ranges(assignment: &str) -> (u32, u32, u32, u32) {
-    let mut split_assignments = assignment.split([',', '-']);
-    return (split_assignments.next().unwrap().parse::<u32>().unwrap(), split_assignments.next().unwrap().parse::<u32>().unwrap(), split_assignments.next().unwrap().parse::<u32>().unwrap(), split_assignments.next().unwrap().parse::<u32>().unwrap());
+fn print_coverage(elves: &Elves, k: u32) {
+    let coverage = Coverage::from_assignments(&elves.ranges);
+    for (start, end) in coverage.uncovered() {
+        println!("Uncovered: {}-{}", start, end);
+    }
+    for segment in coverage.covered_by_more_than(k) {
+        println!("Covered by {} elves: {}-{}", segment.elves, segment.start, segment.end);
+    }
+    for elf in redundant_elves(&elves.ranges) {
+        println!("Redundant: {}", elves.describe(elf));
+    }
+
+    let cover = minimal_cover(&elves.ranges);
+    println!("Minimal cover ({} of {} elves):", cover.len(), elves.ranges.len());
+    for elf in cover {
+        println!("  {}", elves.describe(elf));
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum AssignmentParseError {
+    Empty,
+    MissingSeparator { range: String },
+    InvalidSection { section: String },
+    Backwards { start: u32, end: u32 }
+}
+
+impl fmt::Display for AssignmentParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssignmentParseError::Empty => write!(f, "empty range"),
+            AssignmentParseError::MissingSeparator { range } => write!(f, "range '{}' should look like start-end", range),
+            AssignmentParseError::InvalidSection { section } => write!(f, "section '{}' is not a number", section),
+            AssignmentParseError::Backwards { start, end } => write!(f, "range {}-{} ends before it starts", start, end)
+        }
+    }
+}
+
+fn parse_assignment_ranges(assignment: &str) -> Result<Vec<(u32, u32)>, AssignmentParseError> {
+    assignment.split(',').map(parse_assignment_range).collect()
+}
+
+fn parse_assignment_range(range: &str) -> Result<(u32, u32), AssignmentParseError> {
+    if range.is_empty() {
+        return Err(AssignmentParseError::Empty);
+    }
+
+    let (start, end) = range.split_once('-').ok_or(AssignmentParseError::MissingSeparator { range: range.to_string() })?;
+    let parse_section = |section: &str| section.parse::<u32>().map_err(|_| AssignmentParseError::InvalidSection { section: section.to_string() });
+    let (start, end) = (parse_section(start)?, parse_section(end)?);
+    if end < start {
+        return Err(AssignmentParseError::Backwards { start, end });
+    }
+
+    Ok((start, end))
+}
+
+// One range contains every other range in the group. Only the widest range can
+// do that, and anything containing it instead must be an identical range.
+fn does_assignment_group_overlap(group: &[(u32, u32)]) -> bool {
+    let Some((outer_start, outer_end)) = group.iter().max_by_key(|(start, end)| end - start) else { return true };
+    group.iter().all(|(start, end)| does_assignment_pair_overlap(*outer_start, *outer_end, *start, *end))
+}
+
+// Every range in the group shares at least one section. On a line it is enough
+// for each pair to overlap.
+fn does_assignment_group_overlap_simple(group: &[(u32, u32)]) -> bool {
+    group.iter().enumerate().all(|(i, (a_start, a_end))| group[i + 1..].iter().all(|(b_start, b_end)| does_assignment_pair_overlap_simple(*a_start, *a_end, *b_start, *b_end)))
 }
 
 fn does_assignment_pair_overlap_simple(assignment_1_start: u32, assignment_1_end: u32, assignment_2_start: u32, assignment_2_end: u32) -> bool {
@@ -43,7 +173,35 @@ mod tests {
     use super::*;
     #[test]
     fn parses() {
-        assert_eq!((2,4,6,8), parse_assignment_ranges("2-4,6-8"));
+        assert_eq!(Ok(vec![(2,4),(6,8)]), parse_assignment_ranges("2-4,6-8"));
+        assert_eq!(Ok(vec![(2,4)]), parse_assignment_ranges("2-4"));
+        assert_eq!(Ok(vec![(2,4),(6,8),(1,9)]), parse_assignment_ranges("2-4,6-8,1-9"));
+    }
+
+    #[test]
+    fn rejects_bad_ranges() {
+        assert_eq!(Err(AssignmentParseError::Empty), parse_assignment_ranges("2-4,"));
+        assert_eq!(Err(AssignmentParseError::MissingSeparator { range: "24".to_string() }), parse_assignment_ranges("24,6-8"));
+        assert_eq!(Err(AssignmentParseError::InvalidSection { section: "x".to_string() }), parse_assignment_ranges("2-x"));
+        assert_eq!(Err(AssignmentParseError::Backwards { start: 4, end: 2 }), parse_assignment_ranges("4-2"));
+    }
+
+    #[test]
+    fn find_group_overlaps() {
+        assert!(does_assignment_group_overlap(&[(1,9),(2,3),(5,9)]));
+        assert!(does_assignment_group_overlap(&[(2,3),(1,9),(1,9)]));
+        assert!(!does_assignment_group_overlap(&[(1,5),(2,3),(5,9)]));
+        assert!(does_assignment_group_overlap(&[(4,6)]));
+        assert!(does_assignment_group_overlap(&[(2,8),(3,7)]));
+        assert!(!does_assignment_group_overlap(&[(2,4),(6,8)]));
+    }
+
+    #[test]
+    fn find_group_overlap_at_all() {
+        assert!(does_assignment_group_overlap_simple(&[(1,5),(3,7),(5,9)]));
+        assert!(!does_assignment_group_overlap_simple(&[(1,4),(3,7),(5,9)]));
+        assert!(does_assignment_group_overlap_simple(&[(4,6)]));
+        assert!(!does_assignment_group_overlap_simple(&[(2,4),(6,8)]));
     }
 
     #[test]
@@ -0,0 +1,177 @@
+use crate::{get_two_mut, Orders, SortedCargo};
+
+// Stacks keep their top crate at index 0.
+pub trait Crane {
+	fn name(&self) -> String;
+
+	// Carries out one order and returns what it cost.
+	fn execute(&self, stacks: &mut [SortedCargo], order: &Orders) -> u32;
+}
+
+// Lifts one crate at a time, so the moved crates end up reversed.
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+	fn name(&self) -> String {
+		"CrateMover 9000".to_string()
+	}
+
+	fn execute(&self, stacks: &mut [SortedCargo], order: &Orders) -> u32 {
+		let (start, end) = get_two_mut(stacks, order.start_stack - 1, order.end_stack - 1);
+		let elements = start.cargo.drain(0..order.amount_to_move as usize);
+		end.cargo.splice(0..0, elements.rev());
+		order.amount_to_move
+	}
+}
+
+// Lifts every crate in the order at once, keeping their order.
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+	fn name(&self) -> String {
+		"CrateMover 9001".to_string()
+	}
+
+	fn execute(&self, stacks: &mut [SortedCargo], order: &Orders) -> u32 {
+		let (start, end) = get_two_mut(stacks, order.start_stack - 1, order.end_stack - 1);
+		let elements = start.cargo.drain(0..order.amount_to_move as usize);
+		end.cargo.splice(0..0, elements);
+		1
+	}
+}
+
+// Lifts at most `capacity` crates at a time, splitting larger orders into
+// several bulk lifts.
+pub struct CapacityCrane {
+	pub capacity: u32
+}
+
+impl Crane for CapacityCrane {
+	fn name(&self) -> String {
+		format!("capacity {} crane", self.capacity)
+	}
+
+	fn execute(&self, stacks: &mut [SortedCargo], order: &Orders) -> u32 {
+		let mut remaining = order.amount_to_move;
+		let mut lifts = 0;
+		while remaining > 0 {
+			let lift = Orders {
+				amount_to_move: remaining.min(self.capacity),
+				start_stack: order.start_stack,
+				end_stack: order.end_stack,
+				line: order.line
+			};
+			CrateMover9001.execute(stacks, &lift);
+			remaining -= lift.amount_to_move;
+			lifts += 1;
+		}
+
+		lifts
+	}
+}
+
+// Slides the lifted crates in underneath the target stack instead of on top.
+pub struct BottomLoadingCrane;
+
+impl Crane for BottomLoadingCrane {
+	fn name(&self) -> String {
+		"bottom-loading crane".to_string()
+	}
+
+	fn execute(&self, stacks: &mut [SortedCargo], order: &Orders) -> u32 {
+		let (start, end) = get_two_mut(stacks, order.start_stack - 1, order.end_stack - 1);
+		end.cargo.extend(start.cargo.drain(0..order.amount_to_move as usize));
+		1
+	}
+}
+
+// Charges a fixed price for every lift the wrapped crane makes.
+pub struct PricedCrane {
+	pub crane: Box<dyn Crane>,
+	pub cost_per_move: u32
+}
+
+impl Crane for PricedCrane {
+	fn name(&self) -> String {
+		format!("{} at {} per move", self.crane.name(), self.cost_per_move)
+	}
+
+	fn execute(&self, stacks: &mut [SortedCargo], order: &Orders) -> u32 {
+		self.crane.execute(stacks, order) * self.cost_per_move
+	}
+}
+
+// Accepts 9000, 9001, capacity=<n> and bottom.
+pub fn parse_crane(model: &str) -> Option<Box<dyn Crane>> {
+	match model.split_once('=') {
+		Some(("capacity", capacity)) => capacity.parse().ok().filter(|c| *c > 0).map(|capacity| Box::new(CapacityCrane { capacity }) as Box<dyn Crane>),
+		Some(_) => None,
+		None => match model {
+			"9000" => Some(Box::new(CrateMover9000)),
+			"9001" => Some(Box::new(CrateMover9001)),
+			"bottom" => Some(Box::new(BottomLoadingCrane)),
+			_ => None
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn stacks() -> Vec<SortedCargo> {
+		vec![
+			SortedCargo { cargo: vec!['A', 'B', 'C', 'D'] },
+			SortedCargo { cargo: vec!['X'] }
+		]
+	}
+
+	fn move_three() -> Orders {
+		Orders { amount_to_move: 3, start_stack: 1, end_stack: 2, line: 1 }
+	}
+
+	#[test]
+	fn crate_mover_9000() {
+		let mut stacks = stacks();
+		assert_eq!(3, CrateMover9000.execute(&mut stacks, &move_three()));
+		assert_eq!(vec!['C', 'B', 'A', 'X'], stacks[1].cargo);
+		assert_eq!(vec!['D'], stacks[0].cargo);
+	}
+
+	#[test]
+	fn crate_mover_9001() {
+		let mut stacks = stacks();
+		assert_eq!(1, CrateMover9001.execute(&mut stacks, &move_three()));
+		assert_eq!(vec!['A', 'B', 'C', 'X'], stacks[1].cargo);
+	}
+
+	#[test]
+	fn capacity_crane_splits_moves() {
+		let mut stacks = stacks();
+		assert_eq!(2, CapacityCrane { capacity: 2 }.execute(&mut stacks, &move_three()));
+		assert_eq!(vec!['C', 'A', 'B', 'X'], stacks[1].cargo);
+	}
+
+	#[test]
+	fn bottom_loading_crane() {
+		let mut stacks = stacks();
+		assert_eq!(1, BottomLoadingCrane.execute(&mut stacks, &move_three()));
+		assert_eq!(vec!['X', 'A', 'B', 'C'], stacks[1].cargo);
+	}
+
+	#[test]
+	fn priced_crane() {
+		let mut stacks = stacks();
+		let crane = PricedCrane { crane: Box::new(CrateMover9000), cost_per_move: 5 };
+		assert_eq!(15, crane.execute(&mut stacks, &move_three()));
+		assert_eq!(vec!['C', 'B', 'A', 'X'], stacks[1].cargo);
+	}
+
+	#[test]
+	fn parses_models() {
+		assert_eq!("CrateMover 9000", parse_crane("9000").unwrap().name());
+		assert_eq!("capacity 4 crane", parse_crane("capacity=4").unwrap().name());
+		assert!(parse_crane("capacity=0").is_none());
+		assert!(parse_crane("9002").is_none());
+	}
+}
@@ -0,0 +1,81 @@
+use crate::SortedCargo;
+
+// Writes the stacks back out as the puzzle's drawing: one row per crate height,
+// every stack in a three character column and a numbered footer.
+pub fn render_stacks(stacks: &[SortedCargo]) -> String {
+	let height = stacks.iter().map(|s| s.cargo.len()).max().unwrap_or(0);
+
+	let mut rows = vec![];
+	for level in (0..height).rev() {
+		let row = stacks.iter()
+			.map(|stack| {
+				// level 0 is the bottom crate, which sits at the end of the stack
+				if level < stack.cargo.len() {
+					format!("[{}]", stack.cargo[stack.cargo.len() - 1 - level])
+				} else {
+					"   ".to_string()
+				}
+			})
+			.collect::<Vec<String>>()
+			.join(" ");
+		rows.push(row);
+	}
+
+	// labels are centred in the column so two digit numbers keep it three wide
+	let footer = (1..=stacks.len()).map(|i| format!("{:^3}", i)).collect::<Vec<String>>().join(" ");
+	rows.push(footer);
+	rows.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parse_stacks;
+
+	const DRAWING: &str = "    [D]    
+[N] [C]    
+[Z] [M] [P]
+ 1   2   3 ";
+
+	#[test]
+	fn renders_example() {
+		let stacks = vec![
+			SortedCargo { cargo: vec!['N', 'Z'] },
+			SortedCargo { cargo: vec!['D', 'C', 'M'] },
+			SortedCargo { cargo: vec!['P'] }
+		];
+		assert_eq!(DRAWING, render_stacks(&stacks));
+	}
+
+	#[test]
+	fn round_trips() {
+		let stacks = parse_stacks(DRAWING);
+		assert_eq!(DRAWING, render_stacks(&stacks));
+		assert_eq!(stacks, parse_stacks(&render_stacks(&stacks)));
+	}
+
+	#[test]
+	fn round_trips_empty_stacks() {
+		let stacks = vec![
+			SortedCargo { cargo: vec![] },
+			SortedCargo { cargo: vec!['A'] },
+			SortedCargo { cargo: vec![] }
+		];
+		assert_eq!(stacks, parse_stacks(&render_stacks(&stacks)));
+
+		let empty = vec![SortedCargo { cargo: vec![] }; 2];
+		assert_eq!(" 1   2 ", render_stacks(&empty));
+		assert_eq!(empty, parse_stacks(&render_stacks(&empty)));
+	}
+
+	#[test]
+	fn round_trips_two_digit_stacks() {
+		let empty = vec![SortedCargo { cargo: vec![] }; 12];
+		assert_eq!(" 1   2   3   4   5   6   7   8   9  10  11  12 ", render_stacks(&empty));
+		assert_eq!(empty, parse_stacks(&render_stacks(&empty)));
+
+		let mut stacks = empty;
+		stacks[10].cargo = vec!['K', 'X'];
+		assert_eq!(stacks, parse_stacks(&render_stacks(&stacks)));
+	}
+}
@@ -0,0 +1,179 @@
+use crate::crane::Crane;
+use crate::{Orders, SortedCargo};
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum OrderErrorKind {
+	Unreadable,
+	MissingStack { stack: usize },
+	SameStack { stack: usize },
+	NotEnoughCrates { stack: usize, available: usize, requested: u32 }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct OrderError {
+	pub line: usize,
+	pub order: String,
+	pub kind: OrderErrorKind
+}
+
+impl fmt::Display for OrderError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "line {} '{}': ", self.line, self.order)?;
+		match self.kind {
+			OrderErrorKind::Unreadable => write!(f, "expected 'move <amount> from <stack> to <stack>'"),
+			OrderErrorKind::MissingStack { stack } => write!(f, "there is no stack {}", stack),
+			OrderErrorKind::SameStack { stack } => write!(f, "cannot move stack {} onto itself", stack),
+			OrderErrorKind::NotEnoughCrates { stack, available, requested } => write!(f, "stack {} holds {} crates but {} were requested", stack, available, requested)
+		}
+	}
+}
+
+// Checks an order against the stacks before a crane is allowed to touch them.
+pub fn check_order(stacks: &[SortedCargo], order: &Orders) -> Result<(), OrderError> {
+	let fail = |kind| Err(OrderError { line: order.line, order: order.to_string(), kind });
+
+	for stack in [order.start_stack, order.end_stack] {
+		if stack == 0 || stack > stacks.len() {
+			return fail(OrderErrorKind::MissingStack { stack });
+		}
+	}
+	if order.start_stack == order.end_stack {
+		return fail(OrderErrorKind::SameStack { stack: order.start_stack });
+	}
+
+	let available = stacks[order.start_stack - 1].cargo.len();
+	if order.amount_to_move as usize > available {
+		return fail(OrderErrorKind::NotEnoughCrates { stack: order.start_stack, available, requested: order.amount_to_move });
+	}
+
+	Ok(())
+}
+
+// Walks through the orders one at a time, keeping the stacks from before every
+// order so any step can be undone regardless of crane model.
+pub struct Executor<'a> {
+	crane: &'a dyn Crane,
+	orders: &'a [Orders],
+	stacks: Vec<SortedCargo>,
+	cost: u32,
+	history: Vec<(Vec<SortedCargo>, u32)>
+}
+
+impl<'a> Executor<'a> {
+	pub fn new(crane: &'a dyn Crane, stacks: Vec<SortedCargo>, orders: &'a [Orders]) -> Executor<'a> {
+		Executor { crane, orders, stacks, cost: 0, history: vec![] }
+	}
+
+	pub fn stacks(&self) -> &[SortedCargo] {
+		&self.stacks
+	}
+
+	pub fn cost(&self) -> u32 {
+		self.cost
+	}
+
+	// Number of orders carried out so far.
+	pub fn position(&self) -> usize {
+		self.history.len()
+	}
+
+	pub fn order_count(&self) -> usize {
+		self.orders.len()
+	}
+
+	pub fn next_order(&self) -> Option<&'a Orders> {
+		self.orders.get(self.position())
+	}
+
+	// Returns false once every order has been carried out. A failing order
+	// leaves the stacks untouched.
+	pub fn step_forward(&mut self) -> Result<bool, OrderError> {
+		let Some(order) = self.next_order() else { return Ok(false) };
+		check_order(&self.stacks, order)?;
+
+		self.history.push((self.stacks.clone(), self.cost));
+		self.cost += self.crane.execute(&mut self.stacks, order);
+		Ok(true)
+	}
+
+	// Returns false when already back at the start.
+	pub fn step_back(&mut self) -> bool {
+		match self.history.pop() {
+			Some((stacks, cost)) => {
+				self.stacks = stacks;
+				self.cost = cost;
+				true
+			},
+			None => false
+		}
+	}
+
+	pub fn run_to_end(&mut self) -> Result<(), OrderError> {
+		while self.step_forward()? {}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::crane::{CrateMover9000, CrateMover9001};
+
+	fn stacks() -> Vec<SortedCargo> {
+		vec![
+			SortedCargo { cargo: vec!['A', 'B'] },
+			SortedCargo { cargo: vec!['C'] }
+		]
+	}
+
+	fn order(line: usize, amount_to_move: u32, start_stack: usize, end_stack: usize) -> Orders {
+		Orders { amount_to_move, start_stack, end_stack, line }
+	}
+
+	#[test]
+	fn rejects_bad_orders() {
+		let stacks = stacks();
+		assert_eq!(Ok(()), check_order(&stacks, &order(1, 2, 1, 2)));
+		assert_eq!(OrderErrorKind::NotEnoughCrates { stack: 1, available: 2, requested: 3 }, check_order(&stacks, &order(1, 3, 1, 2)).unwrap_err().kind);
+		assert_eq!(OrderErrorKind::SameStack { stack: 2 }, check_order(&stacks, &order(1, 1, 2, 2)).unwrap_err().kind);
+		assert_eq!(OrderErrorKind::MissingStack { stack: 3 }, check_order(&stacks, &order(1, 1, 1, 3)).unwrap_err().kind);
+		assert_eq!(OrderErrorKind::MissingStack { stack: 0 }, check_order(&stacks, &order(1, 1, 0, 1)).unwrap_err().kind);
+	}
+
+	#[test]
+	fn names_offending_line() {
+		let error = check_order(&stacks(), &order(12, 5, 2, 1)).unwrap_err();
+		assert_eq!(12, error.line);
+		assert_eq!("line 12 'move 5 from 2 to 1': stack 2 holds 1 crates but 5 were requested", error.to_string());
+	}
+
+	#[test]
+	fn steps_forward_and_back() {
+		let orders = vec![order(1, 2, 1, 2), order(2, 1, 2, 1)];
+		let mut executor = Executor::new(&CrateMover9000, stacks(), &orders);
+		assert!(!executor.step_back());
+
+		assert_eq!(Ok(true), executor.step_forward());
+		assert_eq!(vec!['B', 'A', 'C'], executor.stacks()[1].cargo);
+		assert_eq!(Ok(true), executor.step_forward());
+		assert_eq!(Ok(false), executor.step_forward());
+		assert_eq!(3, executor.cost());
+
+		assert!(executor.step_back());
+		assert_eq!(1, executor.position());
+		assert_eq!(2, executor.cost());
+		assert!(executor.step_back());
+		assert_eq!(stacks(), executor.stacks());
+		assert_eq!(0, executor.cost());
+	}
+
+	#[test]
+	fn stops_at_failing_order() {
+		let orders = vec![order(1, 1, 1, 2), order(2, 5, 1, 2)];
+		let mut executor = Executor::new(&CrateMover9001, stacks(), &orders);
+		assert_eq!(2, executor.run_to_end().unwrap_err().line);
+		assert_eq!(1, executor.position());
+		assert_eq!(vec!['B'], executor.stacks()[0].cargo);
+	}
+}
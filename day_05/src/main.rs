@@ -1,15 +1,38 @@
 use std::env;
+use std::fmt;
 use std::fs;
+use std::io;
+use std::process;
+
+use crane::{parse_crane, Crane, CrateMover9000, CrateMover9001, PricedCrane};
+use drawing::render_stacks;
+use executor::{check_order, Executor, OrderError, OrderErrorKind};
+use planner::plan_orders;
+
+mod crane;
+mod drawing;
+mod executor;
+mod planner;
 
 #[derive(Debug)]
+#[derive(Clone)]
 struct Orders {
     amount_to_move: u32,
     start_stack: usize,
-    end_stack: usize
+    end_stack: usize,
+    // line of the input file the order came from
+    line: usize
+}
+
+impl fmt::Display for Orders {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.amount_to_move, self.start_stack, self.end_stack)
+    }
 }
 
 #[derive(Debug)]
 #[derive(Clone)]
+#[derive(PartialEq)]
 struct SortedCargo {
     cargo: Vec<char>
 }
@@ -29,77 +52,184 @@ fn main() {
     let args: Vec<String> = env::args().collect();
     let file_path = args[1].clone();
     let contents = fs::read_to_string(file_path).expect("Should have been able to read the file");
-    let (c, i) = parse_starter_stacks(&contents);
+    let (c, i) = match parse_starter_stacks(&contents) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+
+    // day_05 <input> --plan <target> [--crane <9000|9001|...>] [--output <path>]
+    if let Some(target) = find_option(&args, "--plan") {
+        // without --output the puzzle is all that is printed, so it can be read back in
+        let model = find_option(&args, "--crane").map(|m| m.as_str()).unwrap_or("9000");
+        let crane = parse_crane(model).unwrap_or_else(|| panic!("Unknown crane model: {}", model));
+        match plan_orders(crane.as_ref(), &c, target, 200_000) {
+            Ok(plan) => {
+                let puzzle = format!("{}\n\n{}", render_stacks(&c), plan.orders.iter().map(|o| o.to_string()).collect::<Vec<String>>().join("\n"));
+                match find_option(&args, "--output") {
+                    Some(path) => {
+                        fs::write(path, puzzle).expect("Should have been able to write the file");
+                        println!("Wrote {} orders to {} ({})", plan.orders.len(), path, plan.method);
+                    },
+                    None => println!("{}", puzzle)
+                }
+            },
+            Err(e) => {
+                eprintln!("Cannot plan for {}: {}", target, e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    for (problem, crane) in [(1, &CrateMover9000 as &dyn Crane), (2, &CrateMover9001)] {
+        match run_crane(crane, c.clone(), &i) {
+            Ok((stacks, _)) => println!("Problem {}: {}", problem, get_top(stacks)),
+            Err(e) => println!("Problem {}: {}", problem, e)
+        }
+    }
+
+    // day_05 <input> --crane <9000|9001|capacity=N|bottom> [--cost-per-move N]
+    if let Some(model) = find_option(&args, "--crane") {
+        let mut crane = parse_crane(model).unwrap_or_else(|| panic!("Unknown crane model: {}", model));
+        if let Some(cost) = find_option(&args, "--cost-per-move") {
+            crane = Box::new(PricedCrane { crane, cost_per_move: cost.parse().expect("Cost should be a number") });
+        }
+
+        match run_crane(crane.as_ref(), c.clone(), &i) {
+            Ok((stacks, cost)) => println!("{}: {} (cost {})", crane.name(), get_top(stacks), cost),
+            Err(e) => println!("{}: {}", crane.name(), e)
+        }
+    }
+
+    // day_05 <input> --trace <9000|9001|...>
+    if let Some(model) = find_option(&args, "--trace") {
+        let crane = parse_crane(model).unwrap_or_else(|| panic!("Unknown crane model: {}", model));
+        println!("{}\n", render_stacks(&c));
+        if let Err(e) = run_crane_with(crane.as_ref(), c.clone(), &i, |order, stacks| println!("{}\n{}\n", order, render_stacks(stacks))) {
+            println!("{}", e);
+        }
+    }
+
+    // day_05 <input> --step <9000|9001|...>
+    if let Some(model) = find_option(&args, "--step") {
+        let crane = parse_crane(model).unwrap_or_else(|| panic!("Unknown crane model: {}", model));
+        step_interactively(Executor::new(crane.as_ref(), c.clone(), &i));
+    }
+
+}
+
+// Reads commands from stdin: n (or nothing) steps forward, b steps back,
+// r runs to the end or the first bad order, q quits.
+fn step_interactively(mut executor: Executor) {
+    let stdin = io::stdin();
+    loop {
+        println!("{}", render_stacks(executor.stacks()));
+        match executor.next_order() {
+            Some(order) => println!("[{}/{}, cost {}] next: {}", executor.position(), executor.order_count(), executor.cost(), order),
+            None => println!("[{}/{}, cost {}] finished: {}", executor.position(), executor.order_count(), executor.cost(), get_top(executor.stacks().to_vec()))
+        }
 
-    let new_cargo_stack = sort_boxes(c.clone(), &i);
-    let bulk_cargo_stack = sort_boxes_bulk(c.clone(), &i);
+        let mut command = String::new();
+        if stdin.read_line(&mut command).expect("Should have been able to read stdin") == 0 {
+            return;
+        }
 
-    println!("Problem 1: {}", get_top(new_cargo_stack));
-    println!("Problem 2: {}", get_top(bulk_cargo_stack));
+        let result = match command.trim() {
+            "" | "n" => executor.step_forward().map(|_| ()),
+            "b" => {
+                if !executor.step_back() {
+                    println!("Already at the start");
+                }
+                Ok(())
+            },
+            "r" => executor.run_to_end(),
+            "q" => return,
+            other => {
+                println!("Unknown command: {}", other);
+                Ok(())
+            }
+        };
+
+        if let Err(e) = result {
+            println!("{}", e);
+        }
+    }
 }
 
-fn parse_starter_stacks(input: &str) -> (Vec<SortedCargo>, Vec<Orders>) {
+fn find_option<'a>(args: &'a [String], name: &str) -> Option<&'a String> {
+    args.iter().position(|a| a == name).and_then(|i| args.get(i + 1))
+}
+
+fn parse_starter_stacks(input: &str) -> Result<(Vec<SortedCargo>, Vec<Orders>), OrderError> {
     let mut filtered_input = input.split("\n\n");
     let start_set = filtered_input.next().unwrap();
-    let move_set = filtered_input.next().unwrap();
+    let move_set = filtered_input.next().unwrap_or("");
+
+    // orders start after the drawing and the blank line following it
+    let first_order_line = start_set.split('\n').count() + 2;
+    let instructions = move_set.split('\n')
+        .enumerate()
+        .filter(|(_, il)| !il.is_empty())
+        .map(|(line, il)| parse_order(il, first_order_line + line))
+        .collect::<Result<Vec<Orders>, OrderError>>()?;
+
+    Ok((parse_stacks(start_set), instructions))
+}
+
+fn parse_order(text: &str, line: usize) -> Result<Orders, OrderError> {
+    let words = text.split_whitespace().collect::<Vec<&str>>();
+    let order = match words[..] {
+        ["move", amount, "from", from, "to", to] => match (amount.parse(), from.parse(), to.parse()) {
+            (Ok(amount_to_move), Ok(start_stack), Ok(end_stack)) => Some(Orders { amount_to_move, start_stack, end_stack, line }),
+            _ => None
+        },
+        _ => None
+    };
+
+    order.ok_or(OrderError { line, order: text.to_string(), kind: OrderErrorKind::Unreadable })
+}
 
+fn parse_stacks(start_set: &str) -> Vec<SortedCargo> {
     let split_start_set: Vec<&str> = start_set.split('\n').collect();
-    let amount_of_cargo_piles = ((split_start_set[0].len() - 1) / 4)+ 1;
+    // the footer numbers every pile, even when they are all empty
+    let amount_of_cargo_piles = split_start_set[split_start_set.len() - 1].split_whitespace().count();
 
-    let all_cargo = (0..amount_of_cargo_piles)
-        .into_iter()
+    (0..amount_of_cargo_piles)
         .map(|cpi| 
             split_start_set
             .iter()
             .take(split_start_set.len() - 1) // last line is definitions
-            .map(|s| s.chars().skip(cpi * 4 + 1).next())
+            .map(|s| s.chars().nth(cpi * 4 + 1))
             .filter(|c| c.is_some() && c.unwrap() != ' ')
             .map(|c| c.unwrap())
             .collect::<Vec<char>>()
         ).map(|cargo| SortedCargo {
-            cargo: cargo
-        }).collect::<Vec<SortedCargo>>();
- 
-    let instructions = move_set.split('\n')
-        .map(|il| {
-            let mut source = il.split(" from ");
-            let amount = source.next().unwrap().chars().skip(5).collect::<String>().parse().unwrap();
-            let mut mutation = source.next().unwrap().split(" to ");
-            let from = mutation.next().unwrap().parse().unwrap();
-            let to = mutation.next().unwrap().parse().unwrap();
-
-            return Orders {
-                amount_to_move: amount,
-                start_stack: from,
-                end_stack: to
-            };
-        }).collect::<Vec<Orders>>();
-
-    return (all_cargo, instructions);
+            cargo
+        }).collect::<Vec<SortedCargo>>()
 }
 
-fn sort_boxes(mut starting_boxes: Vec<SortedCargo>, operations: &Vec<Orders>) -> Vec<SortedCargo> {
-    for op in operations {
-        let (start, end) = get_two_mut(&mut starting_boxes, op.start_stack - 1, op.end_stack - 1);
-        let elements = start.cargo.drain(0..op.amount_to_move as usize);
-        end.cargo.splice(0..0, elements.into_iter().rev());
-    }
-
-    return starting_boxes;
+fn run_crane(crane: &dyn Crane, starting_boxes: Vec<SortedCargo>, operations: &[Orders]) -> Result<(Vec<SortedCargo>, u32), OrderError> {
+    run_crane_with(crane, starting_boxes, operations, |_, _| {})
 }
 
-fn sort_boxes_bulk(mut starting_boxes: Vec<SortedCargo>, operations: &Vec<Orders>) -> Vec<SortedCargo> {
+// Same as run_crane, but hands the stacks to `after_step` after every order.
+fn run_crane_with(crane: &dyn Crane, mut starting_boxes: Vec<SortedCargo>, operations: &[Orders], mut after_step: impl FnMut(&Orders, &[SortedCargo])) -> Result<(Vec<SortedCargo>, u32), OrderError> {
+    let mut cost = 0;
     for op in operations {
-        let (start, end) = get_two_mut(&mut starting_boxes, op.start_stack - 1, op.end_stack - 1);
-        let elements = start.cargo.drain(0..op.amount_to_move as usize);
-        end.cargo.splice(0..0, elements.into_iter());
+        check_order(&starting_boxes, op)?;
+        cost += crane.execute(&mut starting_boxes, op);
+        after_step(op, &starting_boxes);
     }
 
-    return starting_boxes;
+    Ok((starting_boxes, cost))
 }
 
+// Empty stacks have nothing on top, so they are left out.
 fn get_top(boxes: Vec<SortedCargo>) -> String {
-    return boxes.iter().map(|cs| cs.cargo.first().unwrap()).collect::<String>();
+    boxes.iter().filter_map(|cs| cs.cargo.first()).collect::<String>()
 }
 
 
@@ -125,8 +255,22 @@ mod tests {
 
 move 11 from 1 to 2
 move 11 from 2 to 1";
-        let (cargo, instructions) = parse_starter_stacks(data);
-        let mapped = sort_boxes(cargo, instructions);
+        let (cargo, instructions) = parse_starter_stacks(data).unwrap();
+        let (mapped, cost) = run_crane(&CrateMover9000, cargo.clone(), &instructions).unwrap();
         assert_eq!("ABCDEFGHI", get_top(mapped));
+        assert_eq!(22, cost);
+
+        let (mapped, cost) = run_crane(&CrateMover9001, cargo, &instructions).unwrap();
+        assert_eq!("ABCDEFGHI", get_top(mapped));
+        assert_eq!(2, cost);
+    }
+    #[test]
+    fn reports_unreadable_orders() {
+        let data = " 1   2 \n\nmove 1 from 1 to 2\nmove x from 1 to 2";
+        let error = parse_starter_stacks(data).unwrap_err();
+        assert_eq!(OrderErrorKind::Unreadable, error.kind);
+        assert_eq!("line 4 'move x from 1 to 2': expected 'move <amount> from <stack> to <stack>'", error.to_string());
+
+        assert_eq!(OrderErrorKind::Unreadable, parse_starter_stacks(" 1   2 \n\nmove 1 from 1").unwrap_err().kind);
     }
 }
\ No newline at end of file
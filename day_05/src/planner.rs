@@ -0,0 +1,305 @@
+use crate::crane::Crane;
+use crate::{Orders, SortedCargo};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum PlanError {
+	WrongLength { stacks: usize, target: usize },
+	MissingCrate { label: char },
+	SearchLimit { explored: usize }
+}
+
+impl fmt::Display for PlanError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			PlanError::WrongLength { stacks, target } => write!(f, "target names {} crates but there are {} stacks", target, stacks),
+			PlanError::MissingCrate { label } => write!(f, "not enough [{}] crates to reach the target", label),
+			PlanError::SearchLimit { explored } => write!(f, "gave up after exploring {} arrangements", explored)
+		}
+	}
+}
+
+// How a plan was found, from best to worst.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PlanMethod {
+	Shortest,
+	// the exact search ran out of room and a quicker, greedier one found it
+	Weighted,
+	// built one crate at a time without searching
+	Constructed
+}
+
+impl fmt::Display for PlanMethod {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			PlanMethod::Shortest => write!(f, "shortest"),
+			PlanMethod::Weighted => write!(f, "not shortest, found by a weighted search"),
+			PlanMethod::Constructed => write!(f, "not shortest, built crate by crate")
+		}
+	}
+}
+
+#[derive(Debug)]
+pub struct Plan {
+	// numbered by their position in the plan, starting at 1
+	pub orders: Vec<Orders>,
+	pub method: PlanMethod
+}
+
+// Searches for orders that leave `target` on top of the stacks, one crate per
+// stack. An exact A* search is tried first; if it explores more than
+// `max_states` arrangements the shorter of a weighted search and a plan built
+// crate by crate is used instead.
+pub fn plan_orders(crane: &dyn Crane, stacks: &[SortedCargo], target: &str, max_states: usize) -> Result<Plan, PlanError> {
+	let target = target.chars().collect::<Vec<char>>();
+	if target.len() != stacks.len() {
+		return Err(PlanError::WrongLength { stacks: stacks.len(), target: target.len() });
+	}
+
+	let mut available = HashMap::new();
+	for label in stacks.iter().flat_map(|s| s.cargo.iter()) {
+		*available.entry(*label).or_insert(0) += 1;
+	}
+	for label in target.iter() {
+		let count = available.entry(*label).or_insert(0);
+		if *count == 0 {
+			return Err(PlanError::MissingCrate { label: *label });
+		}
+		*count -= 1;
+	}
+
+	let found = match search(crane, stacks, &target, max_states, 1) {
+		Some(orders) => Some((orders, PlanMethod::Shortest)),
+		None => {
+			let weighted = search(crane, stacks, &target, max_states, 4).map(|orders| (orders, PlanMethod::Weighted));
+			let constructed = construct(crane, stacks, &target).map(|orders| (orders, PlanMethod::Constructed));
+			[weighted, constructed].into_iter().flatten().min_by_key(|(orders, _)| orders.len())
+		}
+	};
+
+	let (mut orders, method) = found.ok_or(PlanError::SearchLimit { explored: max_states * 2 })?;
+	for (position, order) in orders.iter_mut().enumerate() {
+		order.line = position + 1;
+	}
+
+	Ok(Plan { orders, method })
+}
+
+// A move changes the top of two stacks at most, so half the wrong tops
+// (rounded up) never overestimates the orders still needed.
+fn remaining_estimate(stacks: &[Vec<char>], target: &[char]) -> usize {
+	let wrong = stacks.iter().zip(target.iter()).filter(|(stack, label)| stack.first() != Some(label)).count();
+	wrong.div_ceil(2)
+}
+
+// An arrangement of the stacks and the order that first reached it from its
+// parent arrangement.
+struct SearchState {
+	stacks: Vec<Vec<char>>,
+	reached_by: Option<(usize, Orders)>
+}
+
+fn search(crane: &dyn Crane, stacks: &[SortedCargo], target: &[char], max_states: usize, weight: usize) -> Option<Vec<Orders>> {
+	let start = stacks.iter().map(|s| s.cargo.clone()).collect::<Vec<Vec<char>>>();
+
+	let mut states = vec![SearchState { stacks: start.clone(), reached_by: None }];
+	let mut best_depth = HashMap::from([(start.clone(), 0)]);
+	let mut queue = BinaryHeap::from([Reverse((weight * remaining_estimate(&start, target), 0, 0))]);
+
+	while let Some(Reverse((_, depth, id))) = queue.pop() {
+		let current = states[id].stacks.clone();
+		if best_depth[&current] < depth {
+			continue;
+		}
+		if remaining_estimate(&current, target) == 0 {
+			return Some(rebuild_orders(&states, id));
+		}
+		if states.len() > max_states {
+			return None;
+		}
+
+		let mut cargo = current.iter().map(|c| SortedCargo { cargo: c.clone() }).collect::<Vec<SortedCargo>>();
+		for from in 0..current.len() {
+			for to in 0..current.len() {
+				if from == to {
+					continue;
+				}
+				for amount in 1..=current[from].len() {
+					let order = Orders { amount_to_move: amount as u32, start_stack: from + 1, end_stack: to + 1, line: 0 };
+					crane.execute(&mut cargo, &order);
+					let next = cargo.iter().map(|c| c.cargo.clone()).collect::<Vec<Vec<char>>>();
+					for (stack, original) in cargo.iter_mut().zip(current.iter()) {
+						stack.cargo.clone_from(original);
+					}
+
+					if best_depth.get(&next).is_some_and(|d| *d <= depth + 1) {
+						continue;
+					}
+					best_depth.insert(next.clone(), depth + 1);
+					let estimate = depth + 1 + weight * remaining_estimate(&next, target);
+					queue.push(Reverse((estimate, depth + 1, states.len())));
+					states.push(SearchState { stacks: next, reached_by: Some((id, order)) });
+				}
+			}
+		}
+	}
+
+	None
+}
+
+fn rebuild_orders(states: &[SearchState], mut id: usize) -> Vec<Orders> {
+	let mut orders = vec![];
+	while let Some((parent, order)) = &states[id].reached_by {
+		orders.push(order.clone());
+		id = *parent;
+	}
+
+	orders.reverse();
+	orders
+}
+
+// Orders carried out on a copy of the stacks as they are added.
+struct Builder<'a> {
+	crane: &'a dyn Crane,
+	stacks: Vec<SortedCargo>,
+	orders: Vec<Orders>
+}
+
+impl Builder<'_> {
+	fn order(&mut self, amount: usize, from: usize, to: usize) {
+		if amount == 0 {
+			return;
+		}
+		let order = Orders { amount_to_move: amount as u32, start_stack: from + 1, end_stack: to + 1, line: 0 };
+		self.crane.execute(&mut self.stacks, &order);
+		self.orders.push(order);
+	}
+
+	// Parks everything above the `label` crate nearest the top of either stack
+	// on the other one, and returns the stack the crate is now on top of.
+	fn dig(&mut self, label: char, first: usize, second: usize) -> Option<usize> {
+		let depth = |stack: usize| self.stacks[stack].cargo.iter().position(|c| *c == label);
+		let (stack, depth, park) = match (depth(first), depth(second)) {
+			(Some(a), Some(b)) if b < a => (second, b, first),
+			(Some(a), _) => (first, a, second),
+			(None, Some(b)) => (second, b, first),
+			(None, None) => return None
+		};
+
+		self.order(depth, stack, park);
+		Some(stack)
+	}
+}
+
+// Builds a plan without searching. Everything is piled onto the last stack,
+// then each target crate is dug out of the last two stacks and lifted onto
+// its own empty stack. The last three stacks are finished together: the third
+// from last gets its crate with the second to last's crate on top, the last
+// stack has its crate dug out, and a final move hands the top crate across.
+// Needs at least three stacks and a crane that loads crates on top.
+fn construct(crane: &dyn Crane, stacks: &[SortedCargo], target: &[char]) -> Option<Vec<Orders>> {
+	if stacks.len() < 3 {
+		return None;
+	}
+
+	let mut builder = Builder { crane, stacks: stacks.to_vec(), orders: vec![] };
+	let (x, y, z) = (stacks.len() - 3, stacks.len() - 2, stacks.len() - 1);
+	for stack in 0..=x {
+		builder.order(builder.stacks[stack].cargo.len(), stack, z);
+	}
+
+	for (stack, label) in target[..=y].iter().enumerate() {
+		let from = builder.dig(*label, y, z)?;
+		builder.order(1, from, stack.min(x));
+	}
+	if builder.dig(target[z], y, z)? == y {
+		builder.order(1, y, z);
+	}
+	builder.order(1, x, y);
+
+	let tops = builder.stacks.iter().map(|s| s.cargo.first().copied()).collect::<Vec<Option<char>>>();
+	if tops.iter().zip(target.iter()).any(|(top, label)| *top != Some(*label)) {
+		return None;
+	}
+
+	Some(builder.orders)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::crane::{CrateMover9000, CrateMover9001};
+	use crate::{get_top, run_crane};
+
+	fn stacks() -> Vec<SortedCargo> {
+		vec![
+			SortedCargo { cargo: vec!['N', 'Z'] },
+			SortedCargo { cargo: vec!['D', 'C', 'M'] },
+			SortedCargo { cargo: vec!['P'] }
+		]
+	}
+
+	fn check(crane: &dyn Crane, target: &str, expected_orders: usize) {
+		let plan = plan_orders(crane, &stacks(), target, 10_000).unwrap();
+		assert_eq!(PlanMethod::Shortest, plan.method);
+		assert_eq!(expected_orders, plan.orders.len());
+		let (result, _) = run_crane(crane, stacks(), &plan.orders).unwrap();
+		assert_eq!(target, get_top(result));
+	}
+
+	#[test]
+	fn already_there() {
+		check(&CrateMover9000, "NDP", 0);
+	}
+
+	#[test]
+	fn plans_for_both_cranes() {
+		// N goes under the D and C moved onto the third stack
+		check(&CrateMover9000, "ZMC", 2);
+		check(&CrateMover9001, "ZMC", 3);
+	}
+
+	#[test]
+	fn rejects_impossible_targets() {
+		assert_eq!(PlanError::WrongLength { stacks: 3, target: 2 }, plan_orders(&CrateMover9000, &stacks(), "NP", 100).unwrap_err());
+		assert_eq!(PlanError::MissingCrate { label: 'X' }, plan_orders(&CrateMover9000, &stacks(), "NXP", 100).unwrap_err());
+		assert_eq!(PlanError::MissingCrate { label: 'P' }, plan_orders(&CrateMover9000, &stacks(), "PPN", 100).unwrap_err());
+	}
+
+	#[test]
+	fn settles_for_short_plan() {
+		let plan = plan_orders(&CrateMover9001, &stacks(), "ZMC", 60).unwrap();
+		assert_ne!(PlanMethod::Shortest, plan.method);
+		let (result, _) = run_crane(&CrateMover9001, stacks(), &plan.orders).unwrap();
+		assert_eq!("ZMC", get_top(result));
+	}
+
+	#[test]
+	fn numbers_orders_by_position() {
+		let plan = plan_orders(&CrateMover9000, &stacks(), "ZMC", 10_000).unwrap();
+		assert_eq!(vec![1, 2], plan.orders.iter().map(|o| o.line).collect::<Vec<usize>>());
+	}
+
+	#[test]
+	fn constructs_when_searching_is_hopeless() {
+		let stacks = vec![
+			SortedCargo { cargo: "ABCDEFGH".chars().collect() },
+			SortedCargo { cargo: "IJKLMNOP".chars().collect() },
+			SortedCargo { cargo: "QRSTUVWX".chars().collect() },
+			SortedCargo { cargo: "ABCDEFGH".chars().collect() },
+			SortedCargo { cargo: vec![] }
+		];
+		for crane in [&CrateMover9000 as &dyn Crane, &CrateMover9001] {
+			for target in ["HPXAH", "XHAPB", "AAHHX", "XWVUT"] {
+				let orders = construct(crane, &stacks, &target.chars().collect::<Vec<char>>()).unwrap();
+				let (result, _) = run_crane(crane, stacks.clone(), &orders).unwrap();
+				assert_eq!(target, get_top(result));
+			}
+		}
+
+		let plan = plan_orders(&CrateMover9000, &stacks, "HPXAH", 50).unwrap();
+		assert_eq!(PlanMethod::Constructed, plan.method);
+	}
+}
@@ -0,0 +1,199 @@
+use crate::marker::MarkerDetector;
+use std::fmt;
+use std::io::{self, Read};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Protocol {
+	pub name: String,
+	pub marker_length: usize
+}
+
+impl Protocol {
+	pub fn new(name: &str, marker_length: usize) -> Protocol {
+		Protocol { name: name.to_string(), marker_length }
+	}
+
+	// Parses a list such as "packet=4,message=14".
+	pub fn parse_list(list: &str) -> Option<Vec<Protocol>> {
+		list.split(',')
+			.map(|p| {
+				let (name, length) = p.split_once('=')?;
+				let length = length.parse::<usize>().ok().filter(|l| *l > 0)?;
+				Some(Protocol::new(name, length))
+			})
+			.collect()
+	}
+}
+
+// A message runs from the end of its marker up to the start of the next marker
+// of the same protocol, or the end of whatever frame it is nested in.
+#[derive(Debug, PartialEq)]
+pub struct Frame {
+	pub protocol: String,
+	pub marker_start: usize,
+	pub payload_start: usize,
+	pub payload_end: usize,
+	pub payload: String,
+	pub children: Vec<Frame>
+}
+
+impl Frame {
+	fn write_indented(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+		writeln!(f, "{}{} @{} [{}..{}] {}", "  ".repeat(depth), self.protocol, self.marker_start, self.payload_start, self.payload_end, self.payload)?;
+		for child in self.children.iter() {
+			child.write_indented(f, depth + 1)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl fmt::Display for Frame {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		self.write_indented(f, 0)
+	}
+}
+
+struct FlatFrame {
+	level: usize,
+	marker_start: usize,
+	payload_start: usize,
+	payload_end: usize,
+	parent: Option<usize>
+}
+
+// Splits the stream into frames for every protocol. Protocols with longer
+// markers are the outer layers: a frame whose marker starts inside the payload
+// of a longer-marker frame becomes its child. Markers of one protocol never
+// overlap, as the search restarts after each one.
+pub fn decode<R: Read>(mut reader: R, protocols: &[Protocol]) -> io::Result<Vec<Frame>> {
+	let mut stream = vec![];
+	reader.read_to_end(&mut stream)?;
+
+	let mut layers = protocols.iter().collect::<Vec<&Protocol>>();
+	layers.sort_by_key(|p| std::cmp::Reverse(p.marker_length));
+
+	let mut frames = vec![];
+	// frame indices per layer, in stream order
+	let mut by_level = vec![vec![]; layers.len()];
+	for (level, protocol) in layers.iter().enumerate() {
+		let marker_ends = find_marker_ends(&stream, protocol.marker_length);
+		for (i, end) in marker_ends.iter().enumerate() {
+			let payload_end = marker_ends.get(i + 1).map(|next| next - protocol.marker_length).unwrap_or(stream.len());
+			by_level[level].push(frames.len());
+			frames.push(FlatFrame { level, marker_start: end - protocol.marker_length, payload_start: *end, payload_end, parent: None });
+		}
+	}
+
+	for child in 0..frames.len() {
+		let marker_start = frames[child].marker_start;
+		// the innermost enclosing layer wins
+		let parent = (0..frames[child].level).rev().find_map(|level| {
+			let candidates = &by_level[level];
+			let last_started = candidates.partition_point(|p| frames[*p].payload_start <= marker_start).checked_sub(1)?;
+			let parent = candidates[last_started];
+			(marker_start < frames[parent].payload_end).then_some(parent)
+		});
+		if let Some(parent) = parent {
+			frames[child].parent = Some(parent);
+			frames[child].payload_end = frames[child].payload_end.min(frames[parent].payload_end);
+		}
+	}
+
+	Ok(build_tree(&frames, &layers, &stream))
+}
+
+fn build_tree(frames: &[FlatFrame], layers: &[&Protocol], stream: &[u8]) -> Vec<Frame> {
+	let mut roots = vec![];
+	let mut children = vec![vec![]; frames.len()];
+	for (i, frame) in frames.iter().enumerate() {
+		match frame.parent {
+			Some(parent) => children[parent].push(i),
+			None => roots.push(i)
+		}
+	}
+
+	build_frames(&roots, &children, frames, layers, stream)
+}
+
+fn build_frames(indices: &[usize], children: &[Vec<usize>], frames: &[FlatFrame], layers: &[&Protocol], stream: &[u8]) -> Vec<Frame> {
+	let mut built = indices.iter()
+		.map(|i| {
+			let f = &frames[*i];
+			Frame {
+				protocol: layers[f.level].name.clone(),
+				marker_start: f.marker_start,
+				payload_start: f.payload_start,
+				payload_end: f.payload_end,
+				payload: String::from_utf8_lossy(&stream[f.payload_start..f.payload_end]).to_string(),
+				children: build_frames(&children[*i], children, frames, layers, stream)
+			}
+		})
+		.collect::<Vec<Frame>>();
+
+	built.sort_by_key(|f| f.marker_start);
+	built
+}
+
+fn find_marker_ends(stream: &[u8], length: usize) -> Vec<usize> {
+	let mut ends = vec![];
+	let mut detector = MarkerDetector::new(length);
+	for (offset, byte) in stream.iter().enumerate() {
+		if detector.push(*byte) {
+			ends.push(offset + 1);
+			detector = MarkerDetector::new(length);
+		}
+	}
+
+	ends
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn frame(protocol: &str, marker_start: usize, payload_start: usize, payload_end: usize, payload: &str, children: Vec<Frame>) -> Frame {
+		Frame { protocol: protocol.to_string(), marker_start, payload_start, payload_end, payload: payload.to_string(), children }
+	}
+
+	#[test]
+	fn splits_single_protocol() {
+		let frames = decode("aabxxcdyy".as_bytes(), &[Protocol::new("packet", 2)]).unwrap();
+		assert_eq!(vec![
+			frame("packet", 1, 3, 4, "x", vec![]),
+			frame("packet", 4, 6, 6, "", vec![]),
+			frame("packet", 6, 8, 9, "y", vec![])
+		], frames);
+	}
+
+	#[test]
+	fn nests_interleaved_protocols() {
+		let protocols = [Protocol::new("packet", 2), Protocol::new("message", 3)];
+		let frames = decode("aabcaaabaa".as_bytes(), &protocols).unwrap();
+		assert_eq!(vec![
+			frame("message", 1, 4, 10, "aaabaa", vec![
+				frame("packet", 6, 8, 10, "aa", vec![])
+			]),
+			frame("packet", 1, 3, 3, "", vec![]),
+			frame("packet", 3, 5, 6, "a", vec![])
+		], frames);
+	}
+
+	#[test]
+	fn ends_children_with_parent() {
+		let protocols = [Protocol::new("packet", 2), Protocol::new("message", 4)];
+		// the second message marker "awxy" also ends the last packet of the first
+		let frames = decode("aaaabcdeaa1aawxyzqq".as_bytes(), &protocols).unwrap();
+		assert_eq!(frame("message", 3, 7, 12, "eaa1a", vec![
+			frame("packet", 7, 9, 9, "", vec![]),
+			frame("packet", 9, 11, 12, "a", vec![])
+		]), frames[0]);
+	}
+
+	#[test]
+	fn parses_protocol_list() {
+		assert_eq!(Some(vec![Protocol::new("packet", 4), Protocol::new("message", 14)]), Protocol::parse_list("packet=4,message=14"));
+		assert_eq!(None, Protocol::parse_list("packet=0"));
+		assert_eq!(None, Protocol::parse_list("packet"));
+	}
+}
@@ -1,26 +1,52 @@
-use std::collections::HashSet;
 use std::env;
 use std::fs;
 
+use decoder::{decode, Protocol};
+use marker::{find_marker, markers};
+
+mod decoder;
+mod marker;
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let file_path = args[1].clone();
-    let contents = fs::read_to_string(file_path).expect("Should have been able to read the file");
+    let contents = fs::read(file_path).expect("Should have been able to read the file");
+    // the line terminator is not part of the signal
+    let signal = trim_line_end(&contents);
 
-    println!("Problem 1: {}", find_stream(&contents, 4));
-    println!("Problem 2: {}", find_stream(&contents, 14));
-}
+    println!("Problem 1: {}", describe(find_marker(signal, 4).expect("Should have been able to read the file")));
+    println!("Problem 2: {}", describe(find_marker(signal, 14).expect("Should have been able to read the file")));
 
-fn find_stream(input: &str, amount_looking_for: usize) -> usize {
-    let input_mapped = input.chars().collect::<Vec<char>>();
+    // day_06 <input> --all <length>
+    if args.get(2).is_some_and(|a| a == "--all") {
+        let length = args.get(3).map(|l| l.parse::<usize>().expect("Length should be a number")).unwrap_or(4);
+        for offset in markers(signal, length) {
+            println!("{}", offset.expect("Should have been able to read the file"));
+        }
+    }
 
-    for i in 0..input_mapped.len() {
-        if HashSet::<&char>::from_iter(&input_mapped[i..i+amount_looking_for]).len() == amount_looking_for {
-            return i + amount_looking_for;
+    // day_06 <input> --decode [name=length,...]
+    if args.get(2).is_some_and(|a| a == "--decode") {
+        let protocols = match args.get(3) {
+            Some(list) => Protocol::parse_list(list).expect("Protocols should look like packet=4,message=14"),
+            None => vec![Protocol::new("packet", 4), Protocol::new("message", 14)]
+        };
+        for frame in decode(signal, &protocols).expect("Should have been able to read the file") {
+            print!("{}", frame);
         }
     }
+}
+
+fn trim_line_end(stream: &[u8]) -> &[u8] {
+    let stream = stream.strip_suffix(b"\n").unwrap_or(stream);
+    stream.strip_suffix(b"\r").unwrap_or(stream)
+}
 
-    unreachable!();
+fn describe(marker: Option<usize>) -> String {
+    match marker {
+        Some(offset) => offset.to_string(),
+        None => "no marker".to_string()
+    }
 }
 
 #[cfg(test)]
@@ -29,12 +55,26 @@ mod tests {
     #[test]
     fn first() {
         let input = "bvwbjplbgvbhsrlpgdmjqwftvncz";
-        assert_eq!(5, find_stream(&input, 4));
+        assert_eq!(Some(5), find_marker(input.as_bytes(), 4).unwrap());
     }
 
     #[test]
     fn second() {
         let input = "zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw";
-        assert_eq!(11, find_stream(&input, 4));
+        assert_eq!(Some(11), find_marker(input.as_bytes(), 4).unwrap());
+    }
+
+    #[test]
+    fn none() {
+        assert_eq!(None, find_marker("aaaaaaaaaaaaaaaa".as_bytes(), 4).unwrap());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn ignores_the_line_terminator() {
+        assert_eq!(b"aabc", trim_line_end(b"aabc\n"));
+        assert_eq!(b"aabc", trim_line_end(b"aabc\r\n"));
+        assert_eq!(b"aabc", trim_line_end(b"aabc"));
+        // "abc\n" would otherwise be a second marker
+        assert_eq!(vec![4], markers(trim_line_end(b"aabc\n"), 3).map(|m| m.unwrap()).collect::<Vec<usize>>());
+    }
+}
@@ -0,0 +1,126 @@
+use std::io::{self, BufReader, Bytes, Read};
+
+// Tracks the last `length` bytes of a stream in a ring buffer, along with how
+// often each byte value occurs in it, so every new byte is O(1) work.
+pub struct MarkerDetector {
+	window: Vec<u8>,
+	counts: [usize; 256],
+	// byte values appearing more than once in the window
+	repeated: usize,
+	consumed: usize
+}
+
+impl MarkerDetector {
+	pub fn new(length: usize) -> MarkerDetector {
+		assert!(length > 0, "Marker length must be at least 1");
+		MarkerDetector {
+			window: vec![0; length],
+			counts: [0; 256],
+			repeated: 0,
+			consumed: 0
+		}
+	}
+
+	// Feeds one byte and returns whether the last `length` bytes are all different.
+	pub fn push(&mut self, byte: u8) -> bool {
+		let length = self.window.len();
+		let slot = self.consumed % length;
+		if self.consumed >= length {
+			let leaving = self.window[slot] as usize;
+			self.counts[leaving] -= 1;
+			if self.counts[leaving] == 1 {
+				self.repeated -= 1;
+			}
+		}
+
+		self.window[slot] = byte;
+		self.counts[byte as usize] += 1;
+		if self.counts[byte as usize] == 2 {
+			self.repeated += 1;
+		}
+		self.consumed += 1;
+
+		self.consumed >= length && self.repeated == 0
+	}
+
+	// Bytes seen so far, which is the puzzle's answer right after a marker.
+	pub fn consumed(&self) -> usize {
+		self.consumed
+	}
+}
+
+// Yields the offset just past every marker in the stream, overlapping ones included.
+pub struct Markers<R: Read> {
+	bytes: Bytes<BufReader<R>>,
+	detector: MarkerDetector
+}
+
+impl<R: Read> Iterator for Markers<R> {
+	type Item = io::Result<usize>;
+
+	fn next(&mut self) -> Option<io::Result<usize>> {
+		for byte in self.bytes.by_ref() {
+			match byte {
+				Ok(byte) => {
+					if self.detector.push(byte) {
+						return Some(Ok(self.detector.consumed()));
+					}
+				},
+				Err(e) => return Some(Err(e))
+			}
+		}
+
+		None
+	}
+}
+
+pub fn markers<R: Read>(reader: R, length: usize) -> Markers<R> {
+	Markers {
+		bytes: BufReader::new(reader).bytes(),
+		detector: MarkerDetector::new(length)
+	}
+}
+
+pub fn find_marker<R: Read>(reader: R, length: usize) -> io::Result<Option<usize>> {
+	markers(reader, length).next().transpose()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn finds_first_marker() {
+		assert_eq!(Some(7), find_marker("mjqjpqmgbljsphdztnvjfqwrcgsmlb".as_bytes(), 4).unwrap());
+		assert_eq!(Some(19), find_marker("mjqjpqmgbljsphdztnvjfqwrcgsmlb".as_bytes(), 14).unwrap());
+		assert_eq!(Some(26), find_marker("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw".as_bytes(), 14).unwrap());
+	}
+
+	#[test]
+	fn missing_marker() {
+		assert_eq!(None, find_marker("abcabcabc".as_bytes(), 4).unwrap());
+		assert_eq!(None, find_marker("ab".as_bytes(), 4).unwrap());
+		assert_eq!(None, find_marker("".as_bytes(), 1).unwrap());
+	}
+
+	#[test]
+	fn finds_every_marker() {
+		let found = markers("aabcaab".as_bytes(), 3).collect::<io::Result<Vec<usize>>>().unwrap();
+		assert_eq!(vec![4, 5], found);
+		assert_eq!(vec![1, 2, 3], markers("abc".as_bytes(), 1).map(|m| m.unwrap()).collect::<Vec<usize>>());
+	}
+
+	#[test]
+	fn matches_window_scan() {
+		let stream = (0..5000u32).map(|i| b"abcdefghij"[((i * 7919) % 97 % 10) as usize]).collect::<Vec<u8>>();
+		for length in [2, 4, 7, 10] {
+			let expected = (length..=stream.len())
+				.filter(|end| {
+					let window = &stream[end - length..*end];
+					window.iter().enumerate().all(|(i, b)| !window[i + 1..].contains(b))
+				})
+				.collect::<Vec<usize>>();
+			assert_eq!(expected, markers(stream.as_slice(), length).map(|m| m.unwrap()).collect::<Vec<usize>>());
+		}
+	}
+}
@@ -0,0 +1,201 @@
+use crate::AocDirectory;
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum CleanupError {
+	NotEnoughDeletable { deletable: u64, needed: u64 },
+	NoPlanWithin { max_deletions: usize },
+	SearchLimit { explored: usize }
+}
+
+impl fmt::Display for CleanupError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			CleanupError::NotEnoughDeletable { deletable, needed } => write!(f, "only {} can be deleted but {} is needed", deletable, needed),
+			CleanupError::NoPlanWithin { max_deletions } => write!(f, "cannot free enough space with at most {} deletions", max_deletions),
+			CleanupError::SearchLimit { explored } => write!(f, "gave up after exploring {} choices", explored)
+		}
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Deletion {
+	pub path: String,
+	pub size: u64
+}
+
+#[derive(Debug)]
+pub struct CleanupPlan {
+	pub deletions: Vec<Deletion>,
+	pub freed: u64,
+	// false when the search ran out of room before proving nothing smaller works
+	pub optimal: bool
+}
+
+// A file or directory in pre-order, where `end` is the index just past
+// everything inside it.
+struct Candidate {
+	path: String,
+	size: u64,
+	end: usize,
+	is_file: bool,
+	deletable: bool
+}
+
+// Picks files and directories, none inside another, that free at least
+// `needed` while deleting as little as possible. Protected paths are never
+// deleted, and neither is anything containing them. Gives the best plan found
+// so far once `max_steps` choices have been explored.
+pub fn plan_cleanup(root: &AocDirectory, needed: u64, protected: &[String], max_deletions: usize, max_steps: usize) -> Result<CleanupPlan, CleanupError> {
+	let protected = protected.iter().map(|p| normalise(p)).collect::<Vec<String>>();
+	let mut candidates = vec![];
+	collect(root, "/".to_string(), &protected, &mut candidates);
+
+	// most that can still be freed from each index on: the deletable files,
+	// since deleting a directory never frees more than its files
+	let mut freeable = vec![0; candidates.len() + 1];
+	for i in (0..candidates.len()).rev() {
+		let file_size = if candidates[i].is_file && candidates[i].deletable { candidates[i].size } else { 0 };
+		freeable[i] = freeable[i + 1] + file_size;
+	}
+	if freeable[0] < needed {
+		return Err(CleanupError::NotEnoughDeletable { deletable: freeable[0], needed });
+	}
+
+	let mut search = Search { candidates: &candidates, freeable: &freeable, needed, max_deletions, max_steps, steps: 0, chosen: vec![], best: None };
+	search.explore(0, 0);
+
+	let exhausted = search.steps > max_steps;
+	match search.best {
+		Some((freed, chosen)) => Ok(CleanupPlan {
+			deletions: chosen.iter().map(|i| Deletion { path: candidates[*i].path.clone(), size: candidates[*i].size }).collect(),
+			freed,
+			optimal: !exhausted
+		}),
+		None if exhausted => Err(CleanupError::SearchLimit { explored: max_steps }),
+		None => Err(CleanupError::NoPlanWithin { max_deletions })
+	}
+}
+
+struct Search<'a> {
+	candidates: &'a [Candidate],
+	freeable: &'a [u64],
+	needed: u64,
+	max_deletions: usize,
+	max_steps: usize,
+	steps: usize,
+	chosen: Vec<usize>,
+	best: Option<(u64, Vec<usize>)>
+}
+
+impl Search<'_> {
+	fn explore(&mut self, index: usize, freed: u64) {
+		if freed >= self.needed {
+			if self.best.as_ref().is_none_or(|(best, _)| freed < *best) {
+				self.best = Some((freed, self.chosen.clone()));
+			}
+			return;
+		}
+
+		// nothing can beat freeing exactly what is needed
+		if self.best.as_ref().is_some_and(|(best, _)| *best == self.needed) {
+			return;
+		}
+
+		self.steps += 1;
+		if self.steps > self.max_steps || index == self.candidates.len() || self.chosen.len() == self.max_deletions {
+			return;
+		}
+		if freed + self.freeable[index] < self.needed {
+			return;
+		}
+
+		let candidate = &self.candidates[index];
+		let improves = self.best.as_ref().is_none_or(|(best, _)| freed + candidate.size < *best);
+		if candidate.deletable && candidate.size > 0 && improves {
+			self.chosen.push(index);
+			self.explore(candidate.end, freed + candidate.size);
+			self.chosen.pop();
+		}
+
+		// keep it, which for a directory means looking at what is inside
+		self.explore(index + 1, freed);
+	}
+}
+
+fn collect(directory: &AocDirectory, path: String, protected: &[String], candidates: &mut Vec<Candidate>) -> u64 {
+	let index = candidates.len();
+	let deletable = !protected.iter().any(|p| overlaps(&path, p));
+	candidates.push(Candidate { path: path.clone(), size: 0, end: 0, is_file: false, deletable });
+
+	let mut size = 0;
+	for child in directory.directories.iter() {
+		size += collect(child, format!("{}{}/", path, child.name), protected, candidates);
+	}
+	for file in directory.files.iter() {
+		let file_path = format!("{}{}", path, file.name);
+		let deletable = !protected.iter().any(|p| overlaps(&file_path, p));
+		candidates.push(Candidate { path: file_path, size: file.size, end: candidates.len() + 1, is_file: true, deletable });
+		size += file.size;
+	}
+
+	candidates[index].size = size;
+	candidates[index].end = candidates.len();
+	size
+}
+
+fn normalise(path: &str) -> String {
+	format!("/{}", path.trim_start_matches('/'))
+}
+
+// True when deleting `path` would also delete `protected`, or `path` lies
+// inside it. Directory paths end in '/' so prefixes only match whole names.
+fn overlaps(path: &str, protected: &str) -> bool {
+	let protected_dir = format!("{}/", protected.trim_end_matches('/'));
+	protected_dir.starts_with(path) || path.starts_with(&protected_dir) || path == protected
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::shell::tests::shell;
+
+	fn paths(plan: &CleanupPlan) -> Vec<&str> {
+		plan.deletions.iter().map(|d| d.path.as_str()).collect()
+	}
+
+	#[test]
+	fn prefers_single_file_to_directory() {
+		// the puzzle example, where 8381165 has to be freed
+		let plan = plan_cleanup(shell().root(), 8381165, &[], usize::MAX, 100_000).unwrap();
+		assert!(plan.optimal);
+		assert_eq!(vec!["/c.dat"], paths(&plan));
+		assert_eq!(8504156, plan.freed);
+	}
+
+	#[test]
+	fn combines_around_protected_paths() {
+		let protected = vec!["c.dat".to_string()];
+		let plan = plan_cleanup(shell().root(), 8381165, &protected, usize::MAX, 100_000).unwrap();
+		assert_eq!(vec!["/d/j", "/d/d.ext"], paths(&plan));
+		assert_eq!(9686326, plan.freed);
+
+		let plan = plan_cleanup(shell().root(), 8381165, &protected, 1, 100_000).unwrap();
+		assert_eq!(vec!["/b.txt"], paths(&plan));
+	}
+
+	#[test]
+	fn protecting_a_file_keeps_its_directories() {
+		let protected = vec!["/b.txt".to_string(), "/c.dat".to_string(), "/d/j".to_string(), "/d/d.ext".to_string()];
+		let plan = plan_cleanup(shell().root(), 8381165, &protected, usize::MAX, 100_000).unwrap();
+		assert!(!paths(&plan).iter().any(|p| *p == "/" || *p == "/d/"));
+		assert_eq!(vec!["/d/d.log", "/d/k"], paths(&plan));
+	}
+
+	#[test]
+	fn reports_impossible_plans() {
+		let protected = vec!["/d".to_string()];
+		assert_eq!(CleanupError::NotEnoughDeletable { deletable: 23447523, needed: 30000000 }, plan_cleanup(shell().root(), 30000000, &protected, usize::MAX, 100_000).unwrap_err());
+		assert_eq!(CleanupError::NoPlanWithin { max_deletions: 1 }, plan_cleanup(shell().root(), 23000000, &protected, 1, 100_000).unwrap_err());
+	}
+}
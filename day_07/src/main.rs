@@ -1,5 +1,16 @@
+mod cleanup;
+mod report;
+mod shell;
+mod transcript;
+
+use cleanup::plan_cleanup;
+use report::{largest_directories, render_tree, to_json, SortBy};
+use shell::{Shell, ShellError};
 use std::env;
 use std::fs;
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
+use transcript::{materialise, record};
 
 struct AocDirectory {
     name: String,
@@ -9,7 +20,7 @@ struct AocDirectory {
 
 struct AocFile {
     name: String,
-    size: u32
+    size: u64
 }
 
 #[derive(PartialEq)]
@@ -17,75 +28,170 @@ struct AocFile {
 enum Command {
     Ls,
     Cd(String),
-    CdParent
+    CdParent,
+    Mkdir(String),
+    Rm(String),
+    Du(Option<String>),
+    Find(Option<String>, SizeFilter),
+    Tree(Option<String>)
+}
+
+// Follows find: +N is more than N, -N is less than N, N is exactly N.
+#[derive(PartialEq)]
+#[derive(Debug)]
+enum SizeFilter {
+    Above(u64),
+    Below(u64),
+    Exactly(u64)
+}
+
+impl SizeFilter {
+    fn parse(filter: &str) -> Option<SizeFilter> {
+        if let Some(size) = filter.strip_prefix('+') {
+            size.parse().ok().map(SizeFilter::Above)
+        } else if let Some(size) = filter.strip_prefix('-') {
+            size.parse().ok().map(SizeFilter::Below)
+        } else {
+            filter.parse().ok().map(SizeFilter::Exactly)
+        }
+    }
+
+    fn matches(&self, size: u64) -> bool {
+        match self {
+            SizeFilter::Above(limit) => size > *limit,
+            SizeFilter::Below(limit) => size < *limit,
+            SizeFilter::Exactly(limit) => size == *limit
+        }
+    }
 }
 
 #[derive(PartialEq)]
 #[derive(Debug)]
 enum Entry {
-    File((u32, String)),
+    File((u64, String)),
     Dir(String)
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let file_path = args[1].clone();
-    let contents = fs::read_to_string(file_path).expect("Should have been able to read the file");
-    let aoc_fs = build_input(&contents);
+    // a real directory is explored as if it had been listed in a terminal
+    let contents = if Path::new(&file_path).is_dir() {
+        record(Path::new(&file_path)).expect("Should have been able to read the directory")
+    } else {
+        fs::read_to_string(file_path).expect("Should have been able to read the file")
+    };
+    let mut shell = Shell::new();
+    if let Err(e) = shell.replay(&contents) {
+        panic!("Could not replay the transcript: {}", e);
+    }
 
-    let mut all_dir_sizes: u32 = 0;
-    let _ = get_directory_size_with_max(&aoc_fs, &mut all_dir_sizes);
-    println!("Problem 1: {}", all_dir_sizes);
+    if args.get(2).is_some_and(|a| a == "--shell") {
+        run_shell(&mut shell);
+        return;
+    }
 
-    let total_dir_space = get_directory_size(&aoc_fs);
-    let free_space = 70000000 - total_dir_space;
+    // day_07 <input|directory> --transcript
+    if args.iter().any(|a| a == "--transcript") {
+        println!("{}", contents);
+        return;
+    }
 
-    println!("Problem 2: {}", get_smallest_directory_size(&aoc_fs, 30000000 - free_space).1);
-}
+    // day_07 <input|directory> --materialise [target]
+    if let Some(i) = args.iter().position(|a| a == "--materialise") {
+        let target = args.get(i + 1).map(PathBuf::from).unwrap_or_else(|| env::temp_dir().join(format!("day_07_{}", std::process::id())));
+        materialise(shell.root(), &target).expect("Should have been able to write the directory tree");
+        // du counts directories too, so compare with `du -sb --apparent-size` minus their sizes
+        println!("{}: {} bytes in files", target.display(), get_directory_size(shell.root()));
+        return;
+    }
 
-fn build_input(input: &str) -> AocDirectory {
-    let mut root = AocDirectory {
-        name: "/".to_string(),
-        files: vec![],
-        directories: vec![]
-    };
+    let aoc_fs = shell.root();
 
-    build_tree(&mut root, &mut input.split('\n').into_iter().skip(1));
+    // day_07 <input|directory> --tree [--sort <size|name>] [--depth <n>] --json
+    // prints nothing but the JSON, so it can be piped
+    if args.iter().any(|a| a == "--tree") && args.iter().any(|a| a == "--json") {
+        let (sort, depth) = tree_options(&args);
+        println!("{}", to_json(aoc_fs, sort, depth));
+        return;
+    }
 
-    root
-}
+    let mut all_dir_sizes: u64 = 0;
+    let _ = get_directory_size_with_max(aoc_fs, &mut all_dir_sizes);
+    println!("Problem 1: {}", all_dir_sizes);
 
-fn build_tree<'a>(current: &mut AocDirectory, input_iter: &mut dyn Iterator<Item = &str>) {
-    while let Some(input) = input_iter.next() {
-        if input.starts_with('$') {
-            match parse_command(input) {
-                Command::Ls => (), // ignore, what follows is for the current directory
-                Command::CdParent => return,
-                Command::Cd(path) => {
-                    let child = current.directories.iter_mut().find(|d| d.name == path).unwrap();
-                    build_tree(child, input_iter);
-                }
-            }
-        } else {
-            // we have data for the current directory
-            match parse_entry(input) {
-                Entry::File((size, name)) => {
-                    current.files.push(AocFile { name: name, size: size });
-                },
-                Entry::Dir(path) => {
-                    current.directories.push(AocDirectory { name: path, directories: vec![], files: vec![] });
+    // real directories can be far smaller than the puzzle's disk, or bigger
+    let total_dir_space = get_directory_size(aoc_fs);
+    let free_space = 70000000u64.saturating_sub(total_dir_space);
+    let needed = 30000000u64.saturating_sub(free_space);
+
+    if needed == 0 {
+        println!("Problem 2: nothing to free");
+    } else {
+        println!("Problem 2: {}", get_smallest_directory_size(aoc_fs, needed).1);
+    }
+
+    // day_07 <input> --cleanup [--protect <path,path,...>] [--max-deletions <n>]
+    if args.iter().any(|a| a == "--cleanup") {
+        let protected = find_option(&args, "--protect").map(|p| p.split(',').map(|p| p.to_string()).collect()).unwrap_or(vec![]);
+        let max_deletions = find_option(&args, "--max-deletions").map(|m| m.parse().expect("Max deletions should be a number")).unwrap_or(usize::MAX);
+        match plan_cleanup(aoc_fs, needed, &protected, max_deletions, 10_000_000) {
+            Ok(plan) => {
+                for deletion in plan.deletions.iter() {
+                    println!("{}\t{}", deletion.size, deletion.path);
                 }
-            }
+                println!("{}\ttotal{}", plan.freed, if plan.optimal { "" } else { " (best found, may not be smallest)" });
+            },
+            Err(e) => println!("Cleanup: {}", e)
         }
     }
+
+    // day_07 <input> --tree [--sort <size|name>] [--depth <n>]
+    if args.iter().any(|a| a == "--tree") {
+        let (sort, depth) = tree_options(&args);
+        println!("{}", render_tree(aoc_fs, sort, depth));
+    }
+
+    // day_07 <input> --largest <n>
+    if let Some(count) = find_option(&args, "--largest") {
+        println!("{}", largest_directories(aoc_fs, count.parse().expect("Count should be a number")));
+    }
 }
 
+fn tree_options(args: &[String]) -> (SortBy, Option<usize>) {
+    let sort = find_option(args, "--sort").map(|s| SortBy::parse(s).unwrap_or_else(|| panic!("Unknown sort order: {}", s))).unwrap_or(SortBy::Name);
+    let depth = find_option(args, "--depth").map(|d| d.parse().expect("Depth should be a number"));
+    (sort, depth)
+}
+
+fn find_option<'a>(args: &'a [String], name: &str) -> Option<&'a String> {
+    args.iter().position(|a| a == name).and_then(|i| args.get(i + 1))
+}
+
+// Reads commands from stdin against the replayed filesystem, with or without
+// the leading "$ ".
+fn run_shell(shell: &mut Shell) {
+    for line in io::stdin().lock().lines() {
+        let line = line.expect("Should have been able to read stdin");
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let command = if line.starts_with('$') { line.to_string() } else { format!("$ {}", line) };
+        match parse_command(&command).and_then(|c| shell.execute(&c)) {
+            Ok(output) if output.is_empty() => (),
+            Ok(output) => println!("{}", output),
+            Err(e) => eprintln!("{}: {}", shell.cwd(), e)
+        }
+    }
+}
 
-fn get_directory_size_with_max(directory: &AocDirectory, cur_max: &mut u32) -> u32 {
+fn get_directory_size_with_max(directory: &AocDirectory, cur_max: &mut u64) -> u64 {
     // compute raw size of this directory, from all children
     // if this directories raw size is less than the max value, increment the total directory size
-    let file_sizes: u32 = directory.files.iter().map(|f| f.size).sum();
-    let sub_dir_sizes: u32 = directory.directories.iter().map(|d| get_directory_size_with_max(&d, cur_max)).sum();
+    let file_sizes: u64 = directory.files.iter().map(|f| f.size).sum();
+    let sub_dir_sizes: u64 = directory.directories.iter().map(|d| get_directory_size_with_max(d, cur_max)).sum();
 
     let size_of_directory = file_sizes + sub_dir_sizes;
     if size_of_directory <= 100000 {
@@ -95,42 +201,69 @@ fn get_directory_size_with_max(directory: &AocDirectory, cur_max: &mut u32) -> u
     size_of_directory
 }
 
-fn get_smallest_directory_size(directory: &AocDirectory, target_min: u32) -> (u32, u32) {
-    let sizes = directory.directories.iter().map(|d| get_smallest_directory_size(&d, target_min)).collect::<Vec<(u32, u32)>>();
+fn get_smallest_directory_size(directory: &AocDirectory, target_min: u64) -> (u64, u64) {
+    let sizes = directory.directories.iter().map(|d| get_smallest_directory_size(d, target_min)).collect::<Vec<(u64, u64)>>();
 
-    let my_size: u32 = sizes.iter().map(|s| s.0).sum::<u32>() + directory.files.iter().map(|f| f.size).sum::<u32>();
+    let my_size: u64 = sizes.iter().map(|s| s.0).sum::<u64>() + directory.files.iter().map(|f| f.size).sum::<u64>();
 
-    let smallest_child_dir = sizes.iter().map(|s| s.1).filter(|s| *s >= target_min).min_by(|a, b| a.cmp(b)).unwrap_or(u32::MAX);
+    let smallest_child_dir = sizes.iter().map(|s| s.1).filter(|s| *s >= target_min).min_by(|a, b| a.cmp(b)).unwrap_or(u64::MAX);
 
     let smallest = std::cmp::min(my_size, smallest_child_dir);
 
-    let ret = if smallest >= target_min { smallest} else { u32::MAX };
+    let ret = if smallest >= target_min { smallest} else { u64::MAX };
 
-    return (my_size, ret)
+    (my_size, ret)
 }
 
-fn get_directory_size(directory: &AocDirectory) -> u32 {
-    let file_sizes: u32 = directory.files.iter().map(|f| f.size).sum();
-    let sub_dir_sizes: u32 = directory.directories.iter().map(|d| get_directory_size(&d)).sum();
+fn get_directory_size(directory: &AocDirectory) -> u64 {
+    let file_sizes: u64 = directory.files.iter().map(|f| f.size).sum();
+    let sub_dir_sizes: u64 = directory.directories.iter().map(get_directory_size).sum();
     file_sizes + sub_dir_sizes
 }
 
-fn parse_command(line: &str) -> Command {
-    if line.starts_with("$ ls") {
-        return Command::Ls;
-    } else if line.starts_with ("$ cd ..") {
-        return Command::CdParent;
-    } else {
-        return Command::Cd(line.chars().skip(5).collect::<String>());
+// Paths are everything after the command's first space, so names may hold
+// spaces the way `parse_entry` allows.
+fn parse_command(line: &str) -> Result<Command, ShellError> {
+    let unknown = || ShellError::UnknownCommand(line.to_string());
+    let command = line.strip_prefix("$ ").ok_or_else(unknown)?;
+    let (name, path) = match command.split_once(' ') {
+        Some((name, path)) if !path.is_empty() => (name, Some(path)),
+        _ => (command.trim_end(), None)
+    };
+
+    match (name, path) {
+        ("ls", None) => Ok(Command::Ls),
+        ("cd", Some("..")) => Ok(Command::CdParent),
+        ("cd", Some(path)) => Ok(Command::Cd(path.to_string())),
+        ("mkdir", Some(path)) => Ok(Command::Mkdir(path.to_string())),
+        ("rm", Some(path)) => Ok(Command::Rm(path.to_string())),
+        ("du", path) => Ok(Command::Du(path.map(|p| p.to_string()))),
+        ("tree", path) => Ok(Command::Tree(path.map(|p| p.to_string()))),
+        // find [path] -size <filter>
+        ("find", Some(rest)) => {
+            let mut words = rest.rsplitn(3, ' ');
+            let filter = words.next().and_then(SizeFilter::parse).ok_or_else(unknown)?;
+            if words.next() != Some("-size") {
+                return Err(unknown());
+            }
+            Ok(Command::Find(words.next().map(|p| p.to_string()), filter))
+        },
+        _ => Err(unknown())
     }
 }
 
-fn parse_entry(line: &str) -> Entry {
-    if line.starts_with("dir") {
-        return Entry::Dir(line.chars().skip(4).collect::<String>());
+// Names are single path components, so an entry can never point outside the
+// directory it is listed in.
+fn parse_entry(line: &str) -> Result<Entry, ShellError> {
+    let bad = || ShellError::BadEntry(line.to_string());
+    let (first, name) = line.split_once(' ').ok_or_else(bad)?;
+    if matches!(name, "" | "." | "..") || name.contains('/') {
+        return Err(ShellError::BadName(name.to_string()));
+    }
+    if first == "dir" {
+        Ok(Entry::Dir(name.to_string()))
     } else {
-        let mut line = line.split(' ');
-        return Entry::File((line.next().unwrap().parse::<u32>().unwrap(), line.next().unwrap().to_string()))
+        Ok(Entry::File((first.parse::<u64>().map_err(|_| bad())?, name.to_string())))
     }
 }
 
@@ -139,27 +272,46 @@ mod tests {
     use super::*;
     #[test]
     fn parse_command_ls() {
-        assert_eq!(Command::Ls, parse_command("$ ls"));
+        assert_eq!(Ok(Command::Ls), parse_command("$ ls"));
     }
 
     #[test]
     fn parse_command_cd_parent() {
-        assert_eq!(Command::CdParent, parse_command("$ cd .."));
+        assert_eq!(Ok(Command::CdParent), parse_command("$ cd .."));
     }
 
     #[test]
     fn parse_command_cd_path() {
-        assert_eq!(Command::Cd(String::from("foobar")), parse_command("$ cd foobar"));
+        assert_eq!(Ok(Command::Cd(String::from("foobar"))), parse_command("$ cd foobar"));
+        assert_eq!(Ok(Command::Cd(String::from("/"))), parse_command("$ cd /"));
+        assert_eq!(Ok(Command::Cd(String::from("my dir"))), parse_command("$ cd my dir"));
+    }
+
+    #[test]
+    fn parse_command_extras() {
+        assert_eq!(Ok(Command::Mkdir(String::from("/a/b"))), parse_command("$ mkdir /a/b"));
+        assert_eq!(Ok(Command::Du(None)), parse_command("$ du"));
+        assert_eq!(Ok(Command::Find(None, SizeFilter::Above(100))), parse_command("$ find -size +100"));
+        assert_eq!(Ok(Command::Find(Some(String::from("a")), SizeFilter::Exactly(7))), parse_command("$ find a -size 7"));
+        assert_eq!(Ok(Command::Rm(String::from("old notes.txt"))), parse_command("$ rm old notes.txt"));
+    }
+
+    #[test]
+    fn parse_command_unknown() {
+        assert_eq!(Err(ShellError::UnknownCommand(String::from("$ cat a"))), parse_command("$ cat a"));
+        assert_eq!(Err(ShellError::UnknownCommand(String::from("$ cd"))), parse_command("$ cd"));
+        assert_eq!(Err(ShellError::UnknownCommand(String::from("$ find -size x"))), parse_command("$ find -size x"));
     }
 
     #[test]
     fn parse_entry_dir() {
-        assert_eq!(Entry::Dir(String::from("1234")), parse_entry("dir 1234"));
+        assert_eq!(Ok(Entry::Dir(String::from("1234"))), parse_entry("dir 1234"));
     }
 
     #[test]
     fn parse_entry_file() {
-        assert_eq!(Entry::File((54321, "john_jackson.txt".to_string())), parse_entry("54321 john_jackson.txt"));
+        assert_eq!(Ok(Entry::File((54321, "john_jackson.txt".to_string()))), parse_entry("54321 john_jackson.txt"));
+        assert_eq!(Err(ShellError::BadEntry("big a".to_string())), parse_entry("big a"));
     }
 
     #[test]
@@ -171,15 +323,16 @@ dir a
 $ cd a
 $ ls
 1 c.txt
-2 c.txt";
-        let mut root = AocDirectory {
-            name: "/".to_string(),
-            files: vec![],
-            directories: vec![]
-        };
-        build_tree(&mut root, &mut inputs.split('\n').into_iter());
+2 c.txt
+$ cd /
+$ ls
+dir a
+123 b.txt";
+        let mut shell = Shell::new();
+        shell.replay(inputs).unwrap();
+        let root = shell.root();
         assert_eq!(1, root.files.len());
         assert_eq!(1, root.directories.len());
-        assert_eq!(2, root.directories.get(0).unwrap().files.len());
+        assert_eq!(2, root.directories[0].files.len());
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,174 @@
+use crate::{get_directory_size, AocDirectory};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SortBy {
+	Name,
+	Size
+}
+
+impl SortBy {
+	pub fn parse(name: &str) -> Option<SortBy> {
+		match name {
+			"name" => Some(SortBy::Name),
+			"size" => Some(SortBy::Size),
+			_ => None
+		}
+	}
+}
+
+// A directory or file with its cumulative size, children already sorted.
+struct Node<'a> {
+	name: &'a str,
+	size: u64,
+	is_dir: bool,
+	children: Vec<Node<'a>>
+}
+
+fn build(directory: &AocDirectory, sort: SortBy) -> Node<'_> {
+	let mut children = directory.directories.iter().map(|d| build(d, sort)).collect::<Vec<Node>>();
+	children.extend(directory.files.iter().map(|f| Node { name: &f.name, size: f.size, is_dir: false, children: vec![] }));
+	match sort {
+		SortBy::Name => children.sort_by(|a, b| a.name.cmp(b.name)),
+		// biggest first, like du | sort -rn
+		SortBy::Size => children.sort_by(|a, b| b.size.cmp(&a.size).then(a.name.cmp(b.name)))
+	}
+
+	Node { name: &directory.name, size: children.iter().map(|c| c.size).sum(), is_dir: true, children }
+}
+
+fn label(node: &Node) -> String {
+	if node.is_dir && node.name != "/" {
+		format!("{} {}/", node.size, node.name)
+	} else {
+		format!("{} {}", node.size, node.name)
+	}
+}
+
+// Prints like `tree`, with every size including everything below it. Entries
+// deeper than `max_depth` are folded into their directory's size.
+pub fn render_tree(root: &AocDirectory, sort: SortBy, max_depth: Option<usize>) -> String {
+	let node = build(root, sort);
+	let mut lines = vec![label(&node)];
+	render_children(&node, "", 1, max_depth, &mut lines);
+	lines.join("\n")
+}
+
+fn render_children(node: &Node, prefix: &str, depth: usize, max_depth: Option<usize>, lines: &mut Vec<String>) {
+	if max_depth.is_some_and(|max| depth > max) {
+		return;
+	}
+
+	for (i, child) in node.children.iter().enumerate() {
+		let last = i + 1 == node.children.len();
+		lines.push(format!("{}{}{}", prefix, if last { "└── " } else { "├── " }, label(child)));
+		let prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+		render_children(child, &prefix, depth + 1, max_depth, lines);
+	}
+}
+
+pub fn to_json(root: &AocDirectory, sort: SortBy, max_depth: Option<usize>) -> String {
+	let mut json = String::new();
+	write_json(&build(root, sort), 0, max_depth, &mut json);
+	json
+}
+
+fn write_json(node: &Node, depth: usize, max_depth: Option<usize>, json: &mut String) {
+	json.push_str(&format!("{{\"name\":\"{}\",\"type\":\"{}\",\"size\":{}", escape(node.name), if node.is_dir { "dir" } else { "file" }, node.size));
+	if node.is_dir && max_depth.is_none_or(|max| depth < max) {
+		json.push_str(",\"children\":[");
+		for (i, child) in node.children.iter().enumerate() {
+			if i > 0 {
+				json.push(',');
+			}
+			write_json(child, depth + 1, max_depth, json);
+		}
+		json.push(']');
+	}
+	json.push('}');
+}
+
+fn escape(text: &str) -> String {
+	let mut escaped = String::new();
+	for c in text.chars() {
+		match c {
+			'"' => escaped.push_str("\\\""),
+			'\\' => escaped.push_str("\\\\"),
+			c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+			c => escaped.push(c)
+		}
+	}
+	escaped
+}
+
+// The `count` biggest directories below the root, with their share of the
+// whole tree drawn as a bar the way ncdu does.
+pub fn largest_directories(root: &AocDirectory, count: usize) -> String {
+	let mut directories = vec![];
+	collect_directories(root, String::new(), &mut directories);
+	directories.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+	let total = get_directory_size(root).max(1);
+	directories.iter()
+		.take(count)
+		.map(|(path, size)| {
+			let filled = (size * 20 / total) as usize;
+			format!("{:>12} {:>5.1}% [{}{}] {}", size, *size as f64 * 100.0 / total as f64, "#".repeat(filled), " ".repeat(20 - filled), path)
+		})
+		.collect::<Vec<String>>()
+		.join("\n")
+}
+
+fn collect_directories(directory: &AocDirectory, path: String, directories: &mut Vec<(String, u64)>) {
+	for child in directory.directories.iter() {
+		let child_path = format!("{}/{}", path, child.name);
+		directories.push((child_path.clone(), get_directory_size(child)));
+		collect_directories(child, child_path, directories);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::shell::tests::shell;
+
+	#[test]
+	fn renders_by_size_with_depth_limit() {
+		assert_eq!("\
+48381165 /
+├── 24933642 d/
+├── 14848514 b.txt
+├── 8504156 c.dat
+└── 94853 a/", render_tree(shell().root(), SortBy::Size, Some(1)));
+	}
+
+	#[test]
+	fn renders_by_name() {
+		let tree = render_tree(shell().root(), SortBy::Name, None);
+		assert_eq!("\
+48381165 /
+├── 94853 a/
+│   ├── 584 e/
+│   │   └── 584 i
+│   ├── 29116 f
+│   ├── 2557 g
+│   └── 62596 h.lst
+├── 14848514 b.txt", tree.lines().take(8).collect::<Vec<&str>>().join("\n"));
+	}
+
+	#[test]
+	fn exports_json() {
+		assert_eq!(
+			"{\"name\":\"/\",\"type\":\"dir\",\"size\":48381165,\"children\":[{\"name\":\"d\",\"type\":\"dir\",\"size\":24933642},{\"name\":\"b.txt\",\"type\":\"file\",\"size\":14848514},{\"name\":\"c.dat\",\"type\":\"file\",\"size\":8504156},{\"name\":\"a\",\"type\":\"dir\",\"size\":94853}]}",
+			to_json(shell().root(), SortBy::Size, Some(1))
+		);
+		assert_eq!("a\\\"b\\\\c\\u000a", escape("a\"b\\c\n"));
+	}
+
+	#[test]
+	fn lists_largest_directories() {
+		assert_eq!([
+			"    24933642  51.5% [##########          ] /d",
+			"       94853   0.2% [                    ] /a"
+		].join("\n"), largest_directories(shell().root(), 2));
+	}
+}
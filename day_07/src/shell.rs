@@ -0,0 +1,393 @@
+use crate::{get_directory_size, parse_command, parse_entry, AocDirectory, AocFile, Command, Entry, SizeFilter};
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum ShellError {
+	NoSuchDirectory(String),
+	NoSuchEntry(String),
+	AlreadyExists(String),
+	CannotRemoveRoot,
+	UnknownCommand(String),
+	BadEntry(String),
+	BadName(String),
+	OutputWithoutCommand(String)
+}
+
+impl fmt::Display for ShellError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ShellError::NoSuchDirectory(path) => write!(f, "{}: no such directory", path),
+			ShellError::NoSuchEntry(path) => write!(f, "{}: no such file or directory", path),
+			ShellError::AlreadyExists(path) => write!(f, "{}: already exists", path),
+			ShellError::CannotRemoveRoot => write!(f, "refusing to remove /"),
+			ShellError::UnknownCommand(line) => write!(f, "unknown command: {}", line),
+			ShellError::BadEntry(line) => write!(f, "cannot read listing entry: {}", line),
+			ShellError::BadName(name) => write!(f, "'{}' cannot name a file or directory", name),
+			ShellError::OutputWithoutCommand(line) => write!(f, "output before any command: {}", line)
+		}
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub struct TranscriptError {
+	pub line: usize,
+	pub error: ShellError
+}
+
+impl fmt::Display for TranscriptError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "line {}: {}", self.line, self.error)
+	}
+}
+
+// A virtual filesystem driven by the same commands as the puzzle transcript,
+// plus a few extra ones for poking at the result.
+pub struct Shell {
+	root: AocDirectory,
+	cwd: Vec<String>
+}
+
+impl Shell {
+	pub fn new() -> Shell {
+		Shell {
+			root: AocDirectory { name: "/".to_string(), files: vec![], directories: vec![] },
+			cwd: vec![]
+		}
+	}
+
+	pub fn root(&self) -> &AocDirectory {
+		&self.root
+	}
+
+	pub fn cwd(&self) -> String {
+		format_path(&self.cwd)
+	}
+
+	// Lines following `$ ls` replace what was known about the current
+	// directory, so listing it twice never duplicates entries. Subdirectories
+	// that are listed again keep their contents. Output of any other command is
+	// taken as already recorded and skipped.
+	pub fn replay(&mut self, transcript: &str) -> Result<(), TranscriptError> {
+		let mut listing = None;
+		let mut previous = vec![];
+		for (line_number, line) in transcript.split('\n').enumerate() {
+			let fail = |error| TranscriptError { line: line_number + 1, error };
+			if line.is_empty() {
+				continue;
+			}
+
+			if line.starts_with('$') {
+				let command = parse_command(line).map_err(fail)?;
+				listing = Some(command == Command::Ls);
+				if command == Command::Ls {
+					let current = self.directory_mut(&self.cwd.clone()).unwrap();
+					current.files.clear();
+					previous = std::mem::take(&mut current.directories);
+				} else {
+					self.execute(&command).map_err(fail)?;
+				}
+			} else {
+				match listing {
+					Some(true) => self.add_entry(parse_entry(line).map_err(fail)?, &mut previous),
+					Some(false) => (),
+					None => return Err(fail(ShellError::OutputWithoutCommand(line.to_string())))
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	pub fn execute(&mut self, command: &Command) -> Result<String, ShellError> {
+		match command {
+			Command::Ls => Ok(list(self.directory(&self.cwd).unwrap())),
+			Command::CdParent => {
+				self.cwd.pop();
+				Ok(String::new())
+			},
+			Command::Cd(path) => {
+				let target = self.resolve(path);
+				self.directory(&target).ok_or(ShellError::NoSuchDirectory(path.to_string()))?;
+				self.cwd = target;
+				Ok(String::new())
+			},
+			Command::Mkdir(path) => {
+				let mut target = self.resolve(path);
+				let name = target.pop().ok_or(ShellError::AlreadyExists(path.to_string()))?;
+				let parent = self.directory_mut(&target).ok_or(ShellError::NoSuchDirectory(format_path(&target)))?;
+				if parent.directories.iter().any(|d| d.name == name) || parent.files.iter().any(|f| f.name == name) {
+					return Err(ShellError::AlreadyExists(path.to_string()));
+				}
+				parent.directories.push(AocDirectory { name, files: vec![], directories: vec![] });
+				Ok(String::new())
+			},
+			Command::Rm(path) => {
+				let mut target = self.resolve(path);
+				let name = target.pop().ok_or(ShellError::CannotRemoveRoot)?;
+				let parent = self.directory_mut(&target).ok_or(ShellError::NoSuchEntry(path.to_string()))?;
+				let files = parent.files.len();
+				let directories = parent.directories.len();
+				parent.files.retain(|f| f.name != name);
+				parent.directories.retain(|d| d.name != name);
+				if files == parent.files.len() && directories == parent.directories.len() {
+					return Err(ShellError::NoSuchEntry(path.to_string()));
+				}
+
+				// removing a directory we are standing in leaves us at its parent
+				let removed = [target.as_slice(), &[name]].concat();
+				if self.cwd.starts_with(&removed) {
+					self.cwd = target;
+				}
+				Ok(String::new())
+			},
+			Command::Du(path) => {
+				let target = self.resolve(path.as_deref().unwrap_or("."));
+				let directory = self.directory(&target).ok_or(ShellError::NoSuchDirectory(format_path(&target)))?;
+				let mut lines = vec![];
+				disk_usage(directory, &target, &mut lines);
+				Ok(lines.join("\n"))
+			},
+			Command::Find(path, filter) => {
+				let target = self.resolve(path.as_deref().unwrap_or("."));
+				let directory = self.directory(&target).ok_or(ShellError::NoSuchDirectory(format_path(&target)))?;
+				let mut lines = vec![];
+				find_files(directory, &target, filter, &mut lines);
+				Ok(lines.join("\n"))
+			},
+			Command::Tree(path) => {
+				let target = self.resolve(path.as_deref().unwrap_or("."));
+				let directory = self.directory(&target).ok_or(ShellError::NoSuchDirectory(format_path(&target)))?;
+				let mut lines = vec![];
+				draw_tree(directory, 0, &mut lines);
+				Ok(lines.join("\n"))
+			}
+		}
+	}
+
+	fn add_entry(&mut self, entry: Entry, previous: &mut Vec<AocDirectory>) {
+		let current = self.directory_mut(&self.cwd.clone()).unwrap();
+		match entry {
+			Entry::File((size, name)) => current.files.push(AocFile { name, size }),
+			Entry::Dir(name) => {
+				// named twice in one listing
+				if current.directories.iter().any(|d| d.name == name) {
+					return;
+				}
+				let directory = match previous.iter().position(|d| d.name == name) {
+					Some(known) => previous.remove(known),
+					None => AocDirectory { name, files: vec![], directories: vec![] }
+				};
+				current.directories.push(directory);
+			}
+		}
+	}
+
+	// Turns an absolute or relative path into directory names from the root.
+	fn resolve(&self, path: &str) -> Vec<String> {
+		let mut resolved = if path.starts_with('/') { vec![] } else { self.cwd.clone() };
+		for component in path.split('/') {
+			match component {
+				"" | "." => (),
+				".." => {
+					resolved.pop();
+				},
+				name => resolved.push(name.to_string())
+			}
+		}
+
+		resolved
+	}
+
+	fn directory(&self, path: &[String]) -> Option<&AocDirectory> {
+		let mut current = &self.root;
+		for name in path {
+			current = current.directories.iter().find(|d| d.name == *name)?;
+		}
+
+		Some(current)
+	}
+
+	fn directory_mut(&mut self, path: &[String]) -> Option<&mut AocDirectory> {
+		let mut current = &mut self.root;
+		for name in path {
+			current = current.directories.iter_mut().find(|d| d.name == *name)?;
+		}
+
+		Some(current)
+	}
+}
+
+fn format_path(path: &[String]) -> String {
+	format!("/{}", path.join("/"))
+}
+
+fn child_path(path: &[String], name: &str) -> Vec<String> {
+	let mut child = path.to_vec();
+	child.push(name.to_string());
+	child
+}
+
+fn list(directory: &AocDirectory) -> String {
+	let directories = directory.directories.iter().map(|d| format!("dir {}", d.name));
+	let files = directory.files.iter().map(|f| format!("{} {}", f.size, f.name));
+	directories.chain(files).collect::<Vec<String>>().join("\n")
+}
+
+// Same order as du: every directory after its subdirectories.
+fn disk_usage(directory: &AocDirectory, path: &[String], lines: &mut Vec<String>) {
+	for child in directory.directories.iter() {
+		disk_usage(child, &child_path(path, &child.name), lines);
+	}
+	lines.push(format!("{}\t{}", get_directory_size(directory), format_path(path)));
+}
+
+fn find_files(directory: &AocDirectory, path: &[String], filter: &SizeFilter, lines: &mut Vec<String>) {
+	for file in directory.files.iter().filter(|f| filter.matches(f.size)) {
+		lines.push(format_path(&child_path(path, &file.name)));
+	}
+	for child in directory.directories.iter() {
+		find_files(child, &child_path(path, &child.name), filter, lines);
+	}
+}
+
+// Drawn the way the puzzle text shows the example filesystem.
+fn draw_tree(directory: &AocDirectory, depth: usize, lines: &mut Vec<String>) {
+	lines.push(format!("{}- {} (dir)", "  ".repeat(depth), directory.name));
+	for child in directory.directories.iter() {
+		draw_tree(child, depth + 1, lines);
+	}
+	for file in directory.files.iter() {
+		lines.push(format!("{}- {} (file, size={})", "  ".repeat(depth + 1), file.name, file.size));
+	}
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use super::*;
+
+	// The puzzle's example transcript, shared with the other modules' tests.
+	pub(crate) const EXAMPLE: &str = "\
+$ cd /
+$ ls
+dir a
+14848514 b.txt
+8504156 c.dat
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+62596 h.lst
+$ cd e
+$ ls
+584 i
+$ cd ..
+$ cd ..
+$ cd d
+$ ls
+4060174 j
+8033020 d.log
+5626152 d.ext
+7214296 k";
+
+	pub(crate) fn shell() -> Shell {
+		let mut shell = Shell::new();
+		shell.replay(EXAMPLE).unwrap();
+		shell
+	}
+
+	fn run(shell: &mut Shell, line: &str) -> Result<String, ShellError> {
+		shell.execute(&parse_command(line)?)
+	}
+
+	#[test]
+	fn replays_example() {
+		let shell = shell();
+		assert_eq!("/d", shell.cwd());
+		assert_eq!(48381165, get_directory_size(shell.root()));
+	}
+
+	#[test]
+	fn relisting_does_not_duplicate() {
+		let mut shell = shell();
+		shell.replay("$ cd /\n$ ls\ndir a\n14848514 b.txt\n8504156 c.dat\ndir d\n$ cd a\n$ ls\ndir e\n29116 f\n2557 g\n62596 h.lst").unwrap();
+		assert_eq!(48381165, get_directory_size(shell.root()));
+		assert_eq!(2, shell.root().directories.len());
+		assert_eq!(2, shell.root().files.len());
+
+		// entries missing from a new listing are gone
+		shell.replay("$ cd /\n$ ls\ndir a\n14848514 b.txt").unwrap();
+		assert_eq!(14848514 + 94853, get_directory_size(shell.root()));
+	}
+
+	#[test]
+	fn directory_listed_twice_is_kept_once() {
+		let mut shell = shell();
+		shell.replay("$ cd /\n$ ls\ndir a\ndir d\ndir a").unwrap();
+		assert_eq!(vec!["a", "d"], shell.root().directories.iter().map(|d| d.name.as_str()).collect::<Vec<&str>>());
+		assert_eq!(48381165 - 14848514 - 8504156, get_directory_size(shell.root()));
+	}
+
+	#[test]
+	fn absolute_and_relative_paths() {
+		let mut shell = shell();
+		run(&mut shell, "$ cd /a/e").unwrap();
+		assert_eq!("/a/e", shell.cwd());
+		run(&mut shell, "$ cd ../../d").unwrap();
+		assert_eq!("/d", shell.cwd());
+		run(&mut shell, "$ cd /").unwrap();
+		assert_eq!("/", shell.cwd());
+		assert_eq!(Err(ShellError::NoSuchDirectory("nope".to_string())), run(&mut shell, "$ cd nope"));
+		assert_eq!("/", shell.cwd());
+	}
+
+	#[test]
+	fn mkdir_and_rm() {
+		let mut shell = shell();
+		run(&mut shell, "$ mkdir /a/new").unwrap();
+		assert_eq!(Err(ShellError::AlreadyExists("/a/new".to_string())), run(&mut shell, "$ mkdir /a/new"));
+		run(&mut shell, "$ cd /a/new").unwrap();
+
+		run(&mut shell, "$ rm /a").unwrap();
+		assert_eq!("/", shell.cwd());
+		assert_eq!(48381165 - 94853, get_directory_size(shell.root()));
+		run(&mut shell, "$ rm d/k").unwrap();
+		assert_eq!(48381165 - 94853 - 7214296, get_directory_size(shell.root()));
+		assert_eq!(Err(ShellError::NoSuchEntry("d/k".to_string())), run(&mut shell, "$ rm d/k"));
+		assert_eq!(Err(ShellError::CannotRemoveRoot), run(&mut shell, "$ rm /"));
+	}
+
+	#[test]
+	fn du_find_and_tree() {
+		let mut shell = shell();
+		assert_eq!("584\t/a/e\n94853\t/a\n24933642\t/d\n48381165\t/", run(&mut shell, "$ du /").unwrap());
+		assert_eq!("/b.txt\n/c.dat\n/d/d.log", run(&mut shell, "$ find / -size +8000000").unwrap());
+		assert_eq!("/a/e/i", run(&mut shell, "$ find /a -size -1000").unwrap());
+		assert_eq!("/a/g", run(&mut shell, "$ find / -size 2557").unwrap());
+		assert_eq!("\
+- a (dir)
+  - e (dir)
+    - i (file, size=584)
+  - f (file, size=29116)
+  - g (file, size=2557)
+  - h.lst (file, size=62596)", run(&mut shell, "$ tree /a").unwrap());
+	}
+
+	#[test]
+	fn reports_bad_lines() {
+		let mut shell = Shell::new();
+		assert_eq!(Err(TranscriptError { line: 2, error: ShellError::UnknownCommand("$ dance".to_string()) }), shell.replay("$ cd /\n$ dance"));
+		assert_eq!(Err(TranscriptError { line: 1, error: ShellError::OutputWithoutCommand("dir a".to_string()) }), Shell::new().replay("dir a"));
+		assert_eq!(Err(TranscriptError { line: 2, error: ShellError::BadEntry("huge a".to_string()) }), Shell::new().replay("$ ls\nhuge a"));
+		assert_eq!(Err(TranscriptError { line: 2, error: ShellError::NoSuchDirectory("a".to_string()) }), Shell::new().replay("$ ls\n$ cd a"));
+	}
+
+	#[test]
+	fn rejects_hostile_listings() {
+		for (listing, name) in [("0 /tmp/victim.txt", "/tmp/victim.txt"), ("dir ../escape_dir", "../escape_dir"), ("dir ..", ".."), ("12 .", "."), ("dir ", "")] {
+			let error = Shell::new().replay(&format!("$ cd /\n$ ls\n{}", listing)).unwrap_err();
+			assert_eq!(TranscriptError { line: 3, error: ShellError::BadName(name.to_string()) }, error);
+		}
+	}
+}
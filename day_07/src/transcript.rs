@@ -0,0 +1,160 @@
+use crate::AocDirectory;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+// Writes the `$ cd`/`$ ls` session that would explore `root`, entries sorted
+// by name. Symlinks and other special files are left out, as are names the
+// transcript cannot hold.
+pub fn record(root: &Path) -> io::Result<String> {
+	let mut lines = vec!["$ cd /".to_string()];
+	record_directory(root, &mut lines)?;
+	Ok(lines.join("\n"))
+}
+
+fn record_directory(directory: &Path, lines: &mut Vec<String>) -> io::Result<()> {
+	let mut directories = vec![];
+	let mut files = vec![];
+	for entry in fs::read_dir(directory)? {
+		let entry = entry?;
+		let Ok(name) = entry.file_name().into_string() else { continue };
+		if name.contains('\n') {
+			continue;
+		}
+
+		let metadata = fs::symlink_metadata(entry.path())?;
+		if metadata.is_dir() {
+			directories.push(name);
+		} else if metadata.is_file() {
+			files.push((name, metadata.len()));
+		}
+	}
+	directories.sort();
+	files.sort();
+
+	lines.push("$ ls".to_string());
+	lines.extend(directories.iter().map(|d| format!("dir {}", d)));
+	lines.extend(files.iter().map(|(name, size)| format!("{} {}", size, name)));
+
+	for name in directories {
+		lines.push(format!("$ cd {}", name));
+		record_directory(&directory.join(&name), lines)?;
+		lines.push("$ cd ..".to_string());
+	}
+
+	Ok(())
+}
+
+// Recreates the tree under `target` using sparse files, so even the puzzle's
+// multi-megabyte sizes take almost no disk space.
+pub fn materialise(directory: &AocDirectory, target: &Path) -> io::Result<()> {
+	fs::create_dir_all(target)?;
+	for file in directory.files.iter() {
+		File::create(entry_path(target, &file.name)?)?.set_len(file.size)?;
+	}
+	for child in directory.directories.iter() {
+		materialise(child, &entry_path(target, &child.name)?)?;
+	}
+
+	Ok(())
+}
+
+// Joins one entry name onto `target`, refusing anything that would end up
+// outside it: absolute paths, `..`, nested paths and existing symlinks.
+fn entry_path(target: &Path, name: &str) -> io::Result<PathBuf> {
+	let mut components = Path::new(name).components();
+	let path = match (components.next(), components.next()) {
+		(Some(Component::Normal(_)), None) if !name.contains('/') => target.join(name),
+		_ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("'{}' is not a name inside {}", name, target.display())))
+	};
+	if fs::symlink_metadata(&path).is_ok_and(|m| m.file_type().is_symlink()) {
+		return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} is a symlink", path.display())));
+	}
+
+	Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::shell::Shell;
+	use crate::{get_directory_size, parse_command, AocFile};
+
+	fn scratch(name: &str) -> std::path::PathBuf {
+		let path = std::env::temp_dir().join(format!("day_07_{}_{}", name, std::process::id()));
+		let _ = fs::remove_dir_all(&path);
+		path
+	}
+
+	fn du(shell: &mut Shell) -> String {
+		shell.execute(&parse_command("$ du /").unwrap()).unwrap()
+	}
+
+	#[test]
+	fn round_trips_through_real_directories() {
+		let mut original = Shell::new();
+		original.replay("\
+$ cd /
+$ ls
+dir a
+14848514 b.txt
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+$ cd e
+$ ls
+584 i
+$ cd /d
+$ ls
+dir my dir
+5000000000 big file.dat
+$ cd my dir
+$ ls
+12 notes.txt").unwrap();
+
+		let target = scratch("round_trip");
+		materialise(original.root(), &target).unwrap();
+		let transcript = record(&target).unwrap();
+		fs::remove_dir_all(&target).unwrap();
+
+		let mut rebuilt = Shell::new();
+		rebuilt.replay(&transcript).unwrap();
+		assert_eq!(5014878226, get_directory_size(rebuilt.root()));
+		assert_eq!(du(&mut original), du(&mut rebuilt));
+		assert!(transcript.contains("\n$ cd my dir\n"));
+	}
+
+	#[test]
+	fn refuses_to_leave_the_target() {
+		let target = scratch("hostile");
+		let outside = scratch("hostile_outside");
+		for name in ["..", "/tmp/victim.txt", "../escape_dir", "a/../../b", ""] {
+			let root = AocDirectory { name: "/".to_string(), files: vec![AocFile { name: name.to_string(), size: 1 }], directories: vec![] };
+			assert_eq!(io::ErrorKind::InvalidInput, materialise(&root, &target).unwrap_err().kind());
+			let root = AocDirectory { name: "/".to_string(), files: vec![], directories: vec![AocDirectory { name: name.to_string(), files: vec![], directories: vec![] }] };
+			assert_eq!(io::ErrorKind::InvalidInput, materialise(&root, &target).unwrap_err().kind());
+		}
+
+		std::os::unix::fs::symlink(&outside, target.join("link")).unwrap();
+		let root = AocDirectory { name: "/".to_string(), files: vec![AocFile { name: "link".to_string(), size: 1 }], directories: vec![] };
+		assert_eq!(io::ErrorKind::InvalidInput, materialise(&root, &target).unwrap_err().kind());
+		assert!(!outside.exists());
+		assert_eq!(vec!["link"], fs::read_dir(&target).unwrap().map(|e| e.unwrap().file_name().into_string().unwrap()).collect::<Vec<String>>());
+		fs::remove_dir_all(&target).unwrap();
+	}
+
+	#[test]
+	fn records_sorted_listing() {
+		let target = scratch("sorted");
+		fs::create_dir_all(target.join("z")).unwrap();
+		fs::create_dir_all(target.join("b")).unwrap();
+		File::create(target.join("b").join("x")).unwrap().set_len(7).unwrap();
+		File::create(target.join("a")).unwrap().set_len(3).unwrap();
+
+		let transcript = record(&target).unwrap();
+		fs::remove_dir_all(&target).unwrap();
+		assert_eq!("$ cd /\n$ ls\ndir b\ndir z\n3 a\n$ cd b\n$ ls\n7 x\n$ cd ..\n$ cd z\n$ ls\n$ cd ..", transcript);
+	}
+}
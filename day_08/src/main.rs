@@ -1,5 +1,11 @@
+mod render;
+mod visibility;
+
+use render::{ansi_map, heightmap_pgm, overlay_ppm, scenic_heatmap_pgm};
+use std::cell::OnceCell;
 use std::env;
 use std::fs;
+use visibility::{analyse, analyse_with, Blocking, ForestView, Ray};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -8,102 +14,75 @@ fn main() {
 
     println!("Problem 1: {}", count_visible_trees(parse_forest(&contents)));
     println!("Problem 2: {}", find_highest_value_tree(parse_forest(&contents)));
-}
 
-fn parse_forest(input: &str) -> Vec<Vec<u32>> {
-    return input.split('\n').map(|f| f.chars().map(|t| t.to_digit(10).unwrap()).collect::<Vec<u32>>()).collect::<Vec<Vec<u32>>>();
-}
+    // the options below share the sight model chosen by --rays and --blocking,
+    // worked out the first time one of them needs it
+    let forest = parse_forest(&contents);
+    let view_cell = OnceCell::new();
+    let view = || view_cell.get_or_init(|| sight_model(&args, &forest));
+
+    // day_08 <input> --rays <cardinal|compass|dx,dy;...> [--blocking <>=|>>]
+    if args.iter().any(|a| a == "--rays" || a == "--blocking") {
+        println!("Visible: {}", view().visible_count());
+        if let Some((x, y, score)) = view().best_scenic_score() {
+            println!("Best scenic score: {} at {},{}", score, x, y);
+        }
+    }
+
+    // day_08 <input> [--heightmap <file.pgm>] [--overlay <file.ppm>] [--heatmap <file.pgm>] [--ansi]
+    if let Some(path) = find_option(&args, "--heightmap") {
+        write_image(path, heightmap_pgm(&forest));
+    }
+    if let Some(path) = find_option(&args, "--overlay") {
+        write_image(path, overlay_ppm(&forest, view()));
+    }
+    if let Some(path) = find_option(&args, "--heatmap") {
+        write_image(path, scenic_heatmap_pgm(view()));
+    }
+    if args.iter().any(|a| a == "--ansi") {
+        print!("{}", ansi_map(&forest, view()));
+    }
 
-fn count_visible_trees(forest: Vec<Vec<u32>>) -> u32 {
-    let mut invisible_trees = 0;
-
-    for y in 1..forest.len() - 1 {
-        for x in 1..forest[y].len() - 1 {
-            let mut visible_top = true;
-            let mut visible_down = true;
-            let mut visible_left = true;
-            let mut visible_right = true;
-
-            let potential_house = forest[y][x];
-            for left in (0..=(x-1)).rev() {
-                if forest[y][left] >= potential_house {
-                    visible_left = false;
-                    break;
-                }
-
-            }
-            for right in (x+1)..forest[y].len() {
-                if forest[y][right] >= potential_house {
-                    visible_right = false;
-                    break;
-                }
-            }
-            for top in (0..=(y-1)).rev() {
-                if forest[top][x] >= potential_house {
-                    visible_top = false;
-                    break;
-                }
-            }
-            for down in (y+1)..forest.len() {
-                if forest[down][x] >= potential_house {
-                    visible_down = false;
-                    break;
-                }
-            }
-
-            if !visible_down && !visible_top && !visible_left && !visible_right {
-                invisible_trees += 1;
-            }
+    // day_08 <input> --tree <x,y>
+    if let Some(i) = args.iter().position(|a| a == "--tree") {
+        let position = args.get(i + 1).and_then(|p| p.split_once(','));
+        let (x, y) = position.and_then(|(x, y)| Some((x.parse::<usize>().ok()?, y.parse::<usize>().ok()?))).expect("Tree should be given as x,y");
+        let view = view();
+        if view.visible.get(y).and_then(|r| r.get(x)).is_none() {
+            panic!("There is no tree at {},{}", x, y);
+        }
+        println!("Tree {},{}: visible {}, scenic score {}", x, y, view.visible[y][x], view.scenic_scores[y][x]);
+        for direction in view.directions.iter() {
+            println!("  {}: sees {} trees{}", direction.ray, direction.distances[y][x], if direction.visible[y][x] { " to the edge" } else { "" });
         }
     }
+}
 
-    (forest.len() * forest[0].len() - invisible_trees).try_into().unwrap()
+fn write_image(path: &str, image: String) {
+    fs::write(path, image).unwrap_or_else(|e| panic!("Could not write {}: {}", path, e));
 }
 
+fn find_option<'a>(args: &'a [String], name: &str) -> Option<&'a String> {
+    args.iter().position(|a| a == name).and_then(|i| args.get(i + 1))
+}
 
-fn find_highest_value_tree(forest: Vec<Vec<u32>>) -> u32 {
-    let mut highest_value = 0;
-
-    for y in 1..forest.len() - 1 {
-        for x in 1..forest[y].len() - 1 {
-            let mut visible_top = 0;
-            let mut visible_down = 0;
-            let mut visible_left = 0;
-            let mut visible_right = 0;
-
-            let potential_house = forest[y][x];
-            for left in (0..=(x-1)).rev() {
-                visible_left += 1;
-                if forest[y][left] >= potential_house {
-                    break;
-                }
-            }
-            for right in (x+1)..forest[y].len() {
-                visible_right += 1;
-                if forest[y][right] >= potential_house {
-                    break;
-                }
-            }
-            for top in (0..=(y-1)).rev() {
-                visible_top += 1;
-                if forest[top][x] >= potential_house {
-                    break;
-                }
-            }
-            for down in (y+1)..forest.len() {
-                visible_down += 1;
-                if forest[down][x] >= potential_house {
-                    break;
-                }
-            }
-
-            highest_value = std::cmp::max(highest_value, visible_down * visible_top * visible_left * visible_right);
-        }
-    }
+fn sight_model(args: &[String], forest: &[Vec<u32>]) -> ForestView {
+    let rays = find_option(args, "--rays").map(|r| Ray::parse_list(r).unwrap_or_else(|| panic!("Unknown rays: {}", r))).unwrap_or(Ray::CARDINAL.to_vec());
+    let blocking = find_option(args, "--blocking").map(|b| Blocking::parse(b).unwrap_or_else(|| panic!("Unknown blocking rule: {}", b))).unwrap_or(Blocking::AtLeastAsTall);
+    analyse_with(forest, &rays, blocking)
+}
 
-    highest_value
+fn parse_forest(input: &str) -> Vec<Vec<u32>> {
+    input.split('\n').map(|f| f.chars().map(|t| t.to_digit(10).unwrap()).collect::<Vec<u32>>()).collect::<Vec<Vec<u32>>>()
 }
 
+fn count_visible_trees(forest: Vec<Vec<u32>>) -> usize {
+    analyse(&forest).visible_count()
+}
+
+fn find_highest_value_tree(forest: Vec<Vec<u32>>) -> u64 {
+    analyse(&forest).best_scenic_score().map(|(_, _, score)| score).unwrap_or(0)
+}
 
 #[cfg(test)]
 mod tests {
@@ -140,7 +119,7 @@ mod tests {
 65332
 33549
 35390";
-        assert_eq!(21, count_visible_trees(parse_forest(&input)));
+        assert_eq!(21, count_visible_trees(parse_forest(input)));
     }
 
     #[test]
@@ -151,6 +130,6 @@ mod tests {
 65332
 33549
 35390";
-        assert_eq!(8, find_highest_value_tree(parse_forest(&input)));
+        assert_eq!(8, find_highest_value_tree(parse_forest(input)));
     }
 }
\ No newline at end of file
@@ -0,0 +1,130 @@
+use crate::visibility::ForestView;
+
+// Plain-text netpbm images, so no image library is needed. Heights 0-9 are
+// spread over the full grey range.
+pub fn heightmap_pgm(forest: &[Vec<u32>]) -> String {
+	let rows = forest.iter().map(|row| row.iter().map(|h| grey(*h).to_string()).collect::<Vec<String>>().join(" "));
+	image("P2", forest, rows)
+}
+
+// The heightmap in colour: visible trees tinted green and the best scenic spot
+// in red.
+pub fn overlay_ppm(forest: &[Vec<u32>], view: &ForestView) -> String {
+	let best = best_spot(view);
+	let rows = forest.iter().enumerate().map(|(y, row)| {
+		row.iter()
+			.enumerate()
+			.map(|(x, h)| {
+				let grey = grey(*h);
+				if best == Some((x, y)) {
+					"255 0 0".to_string()
+				} else if view.visible[y][x] {
+					format!("{} 255 {}", grey / 2, grey / 2)
+				} else {
+					format!("{} {} {}", grey, grey, grey)
+				}
+			})
+			.collect::<Vec<String>>()
+			.join(" ")
+	});
+	image("P3", forest, rows)
+}
+
+// Scores span many orders of magnitude, so brightness follows the logarithm
+// of the score to keep everything but the single best tree from going black.
+pub fn scenic_heatmap_pgm(view: &ForestView) -> String {
+	let max = view.scenic_scores.iter().flatten().copied().max().unwrap_or(0);
+	let scale = ((max as f64) + 1.0).ln().max(f64::MIN_POSITIVE);
+	let rows = view.scenic_scores.iter().map(|row| {
+		row.iter()
+			.map(|s| ((((*s as f64) + 1.0).ln() / scale * 255.0).round() as u32).to_string())
+			.collect::<Vec<String>>()
+			.join(" ")
+	});
+	image("P2", &view.scenic_scores, rows)
+}
+
+// Heights as digits on a grey background, visible trees in bold green and the
+// best scenic spot on red.
+pub fn ansi_map(forest: &[Vec<u32>], view: &ForestView) -> String {
+	let best = best_spot(view);
+	let mut map = String::new();
+	for (y, row) in forest.iter().enumerate() {
+		for (x, h) in row.iter().enumerate() {
+			let background = if best == Some((x, y)) { 196 } else { 232 + h * 2 };
+			let foreground = if view.visible[y][x] { "1;38;5;46" } else { "38;5;250" };
+			map.push_str(&format!("\x1b[48;5;{};{}m{}", background, foreground, h));
+		}
+		map.push_str("\x1b[0m\n");
+	}
+
+	map
+}
+
+fn grey(height: u32) -> u32 {
+	height.min(9) * 255 / 9
+}
+
+fn best_spot(view: &ForestView) -> Option<(usize, usize)> {
+	view.best_scenic_score().map(|(x, y, _)| (x, y))
+}
+
+fn image<T>(kind: &str, grid: &[Vec<T>], rows: impl Iterator<Item = String>) -> String {
+	let width = grid.first().map(|r| r.len()).unwrap_or(0);
+	let mut image = format!("{}\n{} {}\n255\n", kind, width, grid.len());
+	for row in rows {
+		image.push_str(&row);
+		image.push('\n');
+	}
+
+	image
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parse_forest;
+	use crate::visibility::analyse;
+
+	fn forest() -> Vec<Vec<u32>> {
+		parse_forest("\
+090
+393
+000")
+	}
+
+	#[test]
+	fn writes_heightmap() {
+		assert_eq!("P2\n3 3\n255\n0 255 0\n85 255 85\n0 0 0\n", heightmap_pgm(&forest()));
+	}
+
+	#[test]
+	fn overlays_visible_and_best() {
+		let forest = forest();
+		let view = analyse(&forest);
+		let image = overlay_ppm(&forest, &view);
+		let lines = image.lines().collect::<Vec<&str>>();
+		assert_eq!(["P3", "3 3", "255"], lines[..3]);
+		// every tree is on the edge or, for the middle 9, taller than its
+		// neighbours; the 9 is also the only tree with a view
+		assert_eq!("42 255 42 255 0 0 42 255 42", lines[4]);
+	}
+
+	#[test]
+	fn heatmap_uses_log_scale() {
+		let view = analyse(&parse_forest("00000\n00000\n00900\n00000\n00000"));
+		let image = scenic_heatmap_pgm(&view);
+		// the 9 scores 16 and the flat trees beside it 1
+		assert_eq!("0 62 255 62 0", image.lines().nth(5).unwrap());
+		assert_eq!("0 0 0 0 0", image.lines().nth(3).unwrap());
+	}
+
+	#[test]
+	fn colours_terminal_map() {
+		let forest = forest();
+		let map = ansi_map(&forest, &analyse(&forest));
+		assert_eq!(3, map.lines().count());
+		assert!(map.contains("\x1b[48;5;196;1;38;5;46m9"));
+		assert!(map.starts_with("\x1b[48;5;232;1;38;5;46m0"));
+	}
+}
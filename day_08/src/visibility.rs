@@ -0,0 +1,293 @@
+use std::fmt;
+
+// A line of sight stepping `dx`, `dy` trees at a time, reduced so it stops at
+// every tree it passes straight through. y grows downwards.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Ray {
+	pub dx: i32,
+	pub dy: i32
+}
+
+impl Ray {
+	pub const UP: Ray = Ray { dx: 0, dy: -1 };
+	pub const LEFT: Ray = Ray { dx: -1, dy: 0 };
+	pub const DOWN: Ray = Ray { dx: 0, dy: 1 };
+	pub const RIGHT: Ray = Ray { dx: 1, dy: 0 };
+	pub const CARDINAL: [Ray; 4] = [Ray::UP, Ray::LEFT, Ray::DOWN, Ray::RIGHT];
+	pub const COMPASS: [Ray; 8] = [
+		Ray::UP, Ray { dx: -1, dy: -1 }, Ray::LEFT, Ray { dx: -1, dy: 1 },
+		Ray::DOWN, Ray { dx: 1, dy: 1 }, Ray::RIGHT, Ray { dx: 1, dy: -1 }
+	];
+
+	pub fn new(dx: i32, dy: i32) -> Option<Ray> {
+		let divisor = gcd(dx.unsigned_abs(), dy.unsigned_abs()) as i32;
+		(divisor != 0).then(|| Ray { dx: dx / divisor, dy: dy / divisor })
+	}
+
+	// Accepts "cardinal", "compass", or rays such as "2,1;-1,0".
+	pub fn parse_list(list: &str) -> Option<Vec<Ray>> {
+		match list {
+			"cardinal" => Some(Ray::CARDINAL.to_vec()),
+			"compass" => Some(Ray::COMPASS.to_vec()),
+			_ => list.split(';')
+				.map(|r| {
+					let (dx, dy) = r.split_once(',')?;
+					Ray::new(dx.trim().parse().ok()?, dy.trim().parse().ok()?)
+				})
+				.collect()
+		}
+	}
+}
+
+impl fmt::Display for Ray {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let name = match (self.dx, self.dy) {
+			(0, -1) => "up",
+			(-1, -1) => "up-left",
+			(-1, 0) => "left",
+			(-1, 1) => "down-left",
+			(0, 1) => "down",
+			(1, 1) => "down-right",
+			(1, 0) => "right",
+			(1, -1) => "up-right",
+			(dx, dy) => return write!(f, "{},{}", dx, dy)
+		};
+		write!(f, "{}", name)
+	}
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+	if b == 0 { a } else { gcd(b, a % b) }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Blocking {
+	// the puzzle's rule: a tree of the same height hides the view
+	AtLeastAsTall,
+	// trees can see over others of their own height
+	Taller
+}
+
+impl Blocking {
+	pub fn parse(rule: &str) -> Option<Blocking> {
+		match rule {
+			">=" => Some(Blocking::AtLeastAsTall),
+			">" => Some(Blocking::Taller),
+			_ => None
+		}
+	}
+
+	fn blocks(&self, blocker: u32, tree: u32) -> bool {
+		match self {
+			Blocking::AtLeastAsTall => blocker >= tree,
+			Blocking::Taller => blocker > tree
+		}
+	}
+}
+
+// What every tree sees along one ray, indexed [y][x].
+#[derive(Debug)]
+pub struct RayView {
+	pub ray: Ray,
+	pub visible: Vec<Vec<bool>>,
+	pub distances: Vec<Vec<usize>>
+}
+
+// A tree counts as visible when it can be seen along any of the rays, and its
+// scenic score multiplies the viewing distance along each of them, capped at
+// u64::MAX for many long rays.
+#[derive(Debug)]
+pub struct ForestView {
+	pub directions: Vec<RayView>,
+	pub visible: Vec<Vec<bool>>,
+	pub scenic_scores: Vec<Vec<u64>>
+}
+
+impl ForestView {
+	pub fn visible_count(&self) -> usize {
+		self.visible.iter().flatten().filter(|v| **v).count()
+	}
+
+	// Highest scenic score as (x, y, score), the first one in reading order on
+	// ties.
+	pub fn best_scenic_score(&self) -> Option<(usize, usize, u64)> {
+		let mut best: Option<(usize, usize, u64)> = None;
+		for (y, row) in self.scenic_scores.iter().enumerate() {
+			for (x, score) in row.iter().enumerate() {
+				if best.is_none_or(|(_, _, b)| *score > b) {
+					best = Some((x, y, *score));
+				}
+			}
+		}
+
+		best
+	}
+}
+
+// The puzzle's model: the four cardinal directions, blocked by trees at least
+// as tall.
+pub fn analyse(forest: &[Vec<u32>]) -> ForestView {
+	analyse_with(forest, &Ray::CARDINAL, Blocking::AtLeastAsTall)
+}
+
+pub fn analyse_with(forest: &[Vec<u32>], rays: &[Ray], blocking: Blocking) -> ForestView {
+	let height = forest.len();
+	let width = forest.first().map(|r| r.len()).unwrap_or(0);
+	assert!(forest.iter().all(|r| r.len() == width), "forest rows must all be the same length");
+
+	let directions = rays.iter().map(|ray| look_along(forest, *ray, blocking)).collect::<Vec<RayView>>();
+	let mut visible = vec![vec![false; width]; height];
+	let mut scenic_scores = vec![vec![1u64; width]; height];
+	for direction in directions.iter() {
+		for y in 0..height {
+			for x in 0..width {
+				visible[y][x] |= direction.visible[y][x];
+				scenic_scores[y][x] = scenic_scores[y][x].saturating_mul(direction.distances[y][x] as u64);
+			}
+		}
+	}
+
+	ForestView { directions, visible, scenic_scores }
+}
+
+// Splits the forest into chains of trees lined up along the ray and walks each
+// chain once from its far end, keeping a stack of the trees still able to
+// block the view. Each tree is pushed and popped at most once, so a ray costs
+// O(width * height) however long its steps are.
+pub fn look_along(forest: &[Vec<u32>], ray: Ray, blocking: Blocking) -> RayView {
+	let height = forest.len() as i32;
+	let width = forest.first().map(|r| r.len()).unwrap_or(0) as i32;
+	let inside = |x: i32, y: i32| x >= 0 && y >= 0 && x < width && y < height;
+
+	let mut visible = vec![vec![false; width as usize]; height as usize];
+	let mut distances = vec![vec![0; width as usize]; height as usize];
+	let mut chain = vec![];
+	let mut blockers: Vec<usize> = vec![];
+
+	for start_y in 0..height {
+		for start_x in 0..width {
+			// chains start at the tree nearest the edge the ray points towards
+			if inside(start_x + ray.dx, start_y + ray.dy) {
+				continue;
+			}
+
+			chain.clear();
+			let (mut x, mut y) = (start_x, start_y);
+			while inside(x, y) {
+				chain.push((x as usize, y as usize));
+				x -= ray.dx;
+				y -= ray.dy;
+			}
+
+			blockers.clear();
+			for (i, (x, y)) in chain.iter().enumerate() {
+				let tree = forest[*y][*x];
+				while blockers.last().is_some_and(|b| !blocking.blocks(forest[chain[*b].1][chain[*b].0], tree)) {
+					blockers.pop();
+				}
+
+				match blockers.last() {
+					Some(blocker) => distances[*y][*x] = i - blocker,
+					None => {
+						visible[*y][*x] = true;
+						distances[*y][*x] = i;
+					}
+				}
+				blockers.push(i);
+			}
+		}
+	}
+
+	RayView { ray, visible, distances }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parse_forest;
+
+	const EXAMPLE: &str = "\
+30373
+25512
+65332
+33549
+35390";
+
+	fn distances_at(view: &ForestView, x: usize, y: usize) -> Vec<usize> {
+		view.directions.iter().map(|d| d.distances[y][x]).collect()
+	}
+
+	#[test]
+	fn matches_example() {
+		let view = analyse(&parse_forest(EXAMPLE));
+		assert_eq!(21, view.visible_count());
+		assert_eq!(Some((2, 3, 8)), view.best_scenic_score());
+		assert_eq!(vec![1, 1, 2, 2], distances_at(&view, 2, 1));
+		assert_eq!(vec![2, 2, 1, 2], distances_at(&view, 2, 3));
+		assert_eq!(vec![true, false, true, false, true], view.visible[3]);
+	}
+
+	#[test]
+	fn edges_score_zero() {
+		let view = analyse(&parse_forest(EXAMPLE));
+		assert!(view.scenic_scores[0].iter().all(|s| *s == 0));
+		assert!(view.scenic_scores.iter().all(|r| r[0] == 0 && r[4] == 0));
+	}
+
+	#[test]
+	fn sees_along_diagonals() {
+		let view = analyse_with(&parse_forest(EXAMPLE), &Ray::COMPASS, Blocking::AtLeastAsTall);
+		// the middle 3 is hidden from every side, diagonals included
+		assert!(!view.visible[2][2]);
+		// the 5 at 1,1 looks up-left straight off the edge past the 3
+		assert!(view.directions[1].visible[1][1]);
+		// and down-right over the 3, 4 and 0 to the far corner
+		assert_eq!(3, view.directions[5].distances[1][1]);
+	}
+
+	#[test]
+	fn taller_rule_sees_over_equal_trees() {
+		let forest = parse_forest("\
+555
+555
+555");
+		assert!(!analyse(&forest).visible[1][1]);
+		let view = analyse_with(&forest, &Ray::CARDINAL, Blocking::Taller);
+		assert!(view.visible[1][1]);
+		assert_eq!(vec![1, 1, 1, 1], distances_at(&view, 1, 1));
+	}
+
+	#[test]
+	fn reduces_and_parses_rays() {
+		assert_eq!(Some(Ray { dx: 1, dy: -1 }), Ray::new(3, -3));
+		assert_eq!(None, Ray::new(0, 0));
+		assert_eq!(Some(vec![Ray { dx: 2, dy: 1 }, Ray::LEFT]), Ray::parse_list("4,2;-1,0"));
+		assert_eq!(8, Ray::parse_list("compass").unwrap().len());
+		assert_eq!(None, Ray::parse_list("2"));
+		assert_eq!("up-left 2,1", format!("{} {}", Ray::COMPASS[1], Ray { dx: 2, dy: 1 }));
+	}
+
+	#[test]
+	fn matches_naive_walk_on_large_forest() {
+		let forest = (0..60).map(|y| (0..80).map(|x| (x * 7 + y * 13 + x * y) % 10).collect()).collect::<Vec<Vec<u32>>>();
+		let rays = [Ray::CARDINAL.as_slice(), &Ray::COMPASS, &[Ray { dx: 2, dy: 1 }, Ray { dx: -1, dy: 3 }]].concat();
+		for blocking in [Blocking::AtLeastAsTall, Blocking::Taller] {
+			let view = analyse_with(&forest, &rays, blocking);
+			for (ray, direction) in rays.iter().zip(view.directions.iter()) {
+				for y in 0..forest.len() as i32 {
+					for x in 0..forest[0].len() as i32 {
+						let tree = forest[y as usize][x as usize];
+						let line = (1..)
+							.map(|k| (x + k * ray.dx, y + k * ray.dy))
+							.take_while(|(x, y)| *x >= 0 && *y >= 0 && (*x as usize) < forest[0].len() && (*y as usize) < forest.len())
+							.map(|(x, y)| forest[y as usize][x as usize])
+							.collect::<Vec<u32>>();
+						let distance = line.iter().position(|t| blocking.blocks(*t, tree)).map(|p| p + 1).unwrap_or(line.len());
+						assert_eq!(distance, direction.distances[y as usize][x as usize]);
+						assert_eq!(!line.iter().any(|t| blocking.blocks(*t, tree)), direction.visible[y as usize][x as usize]);
+					}
+				}
+			}
+		}
+	}
+}
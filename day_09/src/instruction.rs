@@ -0,0 +1,272 @@
+use crate::rope::Rope;
+use std::fmt;
+
+// In clockwise order, so turning is just moving around the list.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Direction {
+	Up,
+	UpRight,
+	Right,
+	DownRight,
+	Down,
+	DownLeft,
+	Left,
+	UpLeft
+}
+
+impl Direction {
+	const CLOCKWISE: [Direction; 8] = [
+		Direction::Up, Direction::UpRight, Direction::Right, Direction::DownRight,
+		Direction::Down, Direction::DownLeft, Direction::Left, Direction::UpLeft
+	];
+
+	pub fn vector(&self) -> (i32, i32) {
+		match self {
+			Direction::Up => (0, 1),
+			Direction::UpRight => (1, 1),
+			Direction::Right => (1, 0),
+			Direction::DownRight => (1, -1),
+			Direction::Down => (0, -1),
+			Direction::DownLeft => (-1, -1),
+			Direction::Left => (-1, 0),
+			Direction::UpLeft => (-1, 1)
+		}
+	}
+
+	// Turns clockwise by `eighths` of a full turn; negative goes anticlockwise.
+	pub fn turn(&self, eighths: i32) -> Direction {
+		let current = Direction::CLOCKWISE.iter().position(|d| d == self).unwrap() as i32;
+		Direction::CLOCKWISE[(current + eighths).rem_euclid(8) as usize]
+	}
+
+	fn parse(code: &str) -> Option<Direction> {
+		match code {
+			"U" => Some(Direction::Up),
+			"UR" => Some(Direction::UpRight),
+			"R" => Some(Direction::Right),
+			"DR" => Some(Direction::DownRight),
+			"D" => Some(Direction::Down),
+			"DL" => Some(Direction::DownLeft),
+			"L" => Some(Direction::Left),
+			"UL" => Some(Direction::UpLeft),
+			_ => None
+		}
+	}
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Instruction {
+	// also points the rope's heading this way
+	Move { direction: Direction, amount: u32 },
+	Forward(u32),
+	// in eighths of a full turn, clockwise
+	Turn(i32),
+	Repeat { times: u32, body: Vec<Instruction> }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum InstructionErrorKind {
+	UnknownDirection(String),
+	MissingAmount,
+	BadAmount(String),
+	TrailingInput(String),
+	BadRepeat(String),
+	UnbalancedParentheses,
+	EmptyInstruction
+}
+
+#[derive(Debug, PartialEq)]
+pub struct InstructionError {
+	pub line: usize,
+	pub text: String,
+	pub kind: InstructionErrorKind
+}
+
+impl fmt::Display for InstructionError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "line {} '{}': ", self.line, self.text)?;
+		match &self.kind {
+			InstructionErrorKind::UnknownDirection(code) => write!(f, "unknown direction {}", code),
+			InstructionErrorKind::MissingAmount => write!(f, "missing amount"),
+			InstructionErrorKind::BadAmount(amount) => write!(f, "{} is not a number", amount),
+			InstructionErrorKind::TrailingInput(rest) => write!(f, "unexpected {}", rest),
+			InstructionErrorKind::BadRepeat(count) => write!(f, "cannot repeat {} times", count),
+			InstructionErrorKind::UnbalancedParentheses => write!(f, "unbalanced parentheses"),
+			InstructionErrorKind::EmptyInstruction => write!(f, "empty instruction")
+		}
+	}
+}
+
+// One or more comma-separated instructions per line:
+//   U 4, UR 2       moves in any of eight directions
+//   TL, TR 1        turns left or right, by 90 degrees or a number of eighths
+//   F 3             moves forward in the direction last moved or turned to
+//   3x(R 4, U 2)    repeats a block, which may itself hold repeats
+pub fn parse_instructions(input: &str) -> Result<Vec<Instruction>, InstructionError> {
+	let mut instructions = vec![];
+	for (line_number, line) in input.split('\n').enumerate() {
+		if line.trim().is_empty() {
+			continue;
+		}
+
+		let parsed = parse_list(line).map_err(|kind| InstructionError { line: line_number + 1, text: line.to_string(), kind })?;
+		instructions.extend(parsed);
+	}
+
+	Ok(instructions)
+}
+
+fn parse_list(text: &str) -> Result<Vec<Instruction>, InstructionErrorKind> {
+	let mut items = vec![];
+	let mut depth = 0;
+	let mut start = 0;
+	for (i, c) in text.char_indices() {
+		match c {
+			'(' => depth += 1,
+			')' if depth == 0 => return Err(InstructionErrorKind::UnbalancedParentheses),
+			')' => depth -= 1,
+			',' if depth == 0 => {
+				items.push(&text[start..i]);
+				start = i + 1;
+			},
+			_ => ()
+		}
+	}
+	if depth != 0 {
+		return Err(InstructionErrorKind::UnbalancedParentheses);
+	}
+	items.push(&text[start..]);
+
+	items.iter().map(|item| parse_item(item.trim())).collect()
+}
+
+fn parse_item(item: &str) -> Result<Instruction, InstructionErrorKind> {
+	if let Some((times, rest)) = item.split_once("x(") {
+		let body = rest.strip_suffix(')').ok_or(InstructionErrorKind::UnbalancedParentheses)?;
+		let times = times.trim().parse::<u32>().map_err(|_| InstructionErrorKind::BadRepeat(times.trim().to_string()))?;
+		return Ok(Instruction::Repeat { times, body: parse_list(body)? });
+	}
+
+	let mut words = item.split_whitespace();
+	let code = words.next().ok_or(InstructionErrorKind::EmptyInstruction)?;
+	let amount = words.next().map(|a| a.parse::<u32>().map_err(|_| InstructionErrorKind::BadAmount(a.to_string()))).transpose()?;
+	if let Some(rest) = words.next() {
+		return Err(InstructionErrorKind::TrailingInput(rest.to_string()));
+	}
+
+	match code {
+		"F" => Ok(Instruction::Forward(amount.ok_or(InstructionErrorKind::MissingAmount)?)),
+		"TL" => Ok(Instruction::Turn(-(amount.unwrap_or(2) as i32))),
+		"TR" => Ok(Instruction::Turn(amount.unwrap_or(2) as i32)),
+		_ => {
+			let direction = Direction::parse(code).ok_or(InstructionErrorKind::UnknownDirection(code.to_string()))?;
+			Ok(Instruction::Move { direction, amount: amount.ok_or(InstructionErrorKind::MissingAmount)? })
+		}
+	}
+}
+
+// Runs the instructions one head step at a time. The heading starts up and
+// is what `Forward` follows.
+pub fn execute(instructions: &[Instruction], rope: &mut Rope) {
+	execute_with(instructions, rope, |_| ());
+}
+
+// Same as `execute`, handing the rope to `after_step` after every step.
+pub fn execute_with(instructions: &[Instruction], rope: &mut Rope, mut after_step: impl FnMut(&Rope)) {
+	let mut heading = Direction::Up;
+	execute_block(instructions, rope, &mut heading, &mut after_step);
+}
+
+fn execute_block(instructions: &[Instruction], rope: &mut Rope, heading: &mut Direction, after_step: &mut impl FnMut(&Rope)) {
+	for instruction in instructions {
+		match instruction {
+			Instruction::Move { direction, amount } => {
+				*heading = *direction;
+				for _ in 0..*amount {
+					rope.step(direction);
+					after_step(rope);
+				}
+			},
+			Instruction::Forward(amount) => {
+				for _ in 0..*amount {
+					rope.step(heading);
+					after_step(rope);
+				}
+			},
+			Instruction::Turn(eighths) => *heading = heading.turn(*eighths),
+			Instruction::Repeat { times, body } => {
+				for _ in 0..*times {
+					execute_block(body, rope, heading, after_step);
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn parse_error(input: &str) -> InstructionErrorKind {
+		parse_instructions(input).unwrap_err().kind
+	}
+
+	#[test]
+	fn parses_moves_turns_and_repeats() {
+		assert_eq!(vec![
+			Instruction::Move { direction: Direction::Right, amount: 4 },
+			Instruction::Move { direction: Direction::DownLeft, amount: 2 },
+			Instruction::Turn(-2),
+			Instruction::Turn(1),
+			Instruction::Forward(3),
+			Instruction::Repeat { times: 3, body: vec![
+				Instruction::Move { direction: Direction::Right, amount: 4 },
+				Instruction::Repeat { times: 2, body: vec![Instruction::Move { direction: Direction::Up, amount: 2 }] }
+			] }
+		], parse_instructions("R 4\nDL 2, TL\n\nTR 1, F 3\n3x(R 4, 2x(U 2))\n").unwrap());
+	}
+
+	#[test]
+	fn reports_bad_lines() {
+		assert_eq!(InstructionErrorKind::UnknownDirection("X".to_string()), parse_error("X 4"));
+		assert_eq!(InstructionErrorKind::MissingAmount, parse_error("U"));
+		assert_eq!(InstructionErrorKind::BadAmount("four".to_string()), parse_error("U four"));
+		assert_eq!(InstructionErrorKind::TrailingInput("5".to_string()), parse_error("U 4 5"));
+		assert_eq!(InstructionErrorKind::BadRepeat("many".to_string()), parse_error("manyx(U 1)"));
+		assert_eq!(InstructionErrorKind::UnbalancedParentheses, parse_error("2x(U 1"));
+		assert_eq!(InstructionErrorKind::EmptyInstruction, parse_error("U 1,,U 2"));
+
+		let error = parse_instructions("U 1\nR 2\nQ 3").unwrap_err();
+		assert_eq!(3, error.line);
+		assert_eq!("line 3 'Q 3': unknown direction Q", error.to_string());
+	}
+
+	#[test]
+	fn turns_around_the_compass() {
+		assert_eq!(Direction::Left, Direction::Up.turn(-2));
+		assert_eq!(Direction::UpRight, Direction::UpLeft.turn(2));
+		assert_eq!(Direction::Down, Direction::Up.turn(12));
+	}
+
+	#[test]
+	fn runs_relative_and_repeated_moves() {
+		let mut rope = Rope::new(2);
+		// a square walked with turns ends where it started
+		execute(&parse_instructions("R 3, 3x(TL, F 3)").unwrap(), &mut rope);
+		assert_eq!((0, 0), rope.knots()[0]);
+
+		let mut rope = Rope::new(2);
+		execute(&parse_instructions("UR 3, F 1, TR 4, F 2").unwrap(), &mut rope);
+		assert_eq!(&[(2, 2), (3, 3)], rope.knots());
+	}
+
+	#[test]
+	fn repeats_match_the_long_hand() {
+		let mut repeated = Rope::new(10).track(9);
+		execute(&parse_instructions("3x(R 4, U 2, 2x(DL 3))").unwrap(), &mut repeated);
+		let mut written_out = Rope::new(10).track(9);
+		execute(&parse_instructions("R 4\nU 2\nDL 3\nDL 3\n".repeat(3).as_str()).unwrap(), &mut written_out);
+		assert_eq!(written_out.knots(), repeated.knots());
+		assert_eq!(written_out.visited(9), repeated.visited(9));
+	}
+}
@@ -1,21 +1,13 @@
-use std::collections::HashSet;
+mod instruction;
+mod render;
+mod rope;
+
+use instruction::{execute, execute_with, parse_instructions};
+use render::{draw_frames, draw_visited};
+use rope::{Attachment, Rope};
 use std::env;
 use std::fs;
 
-#[derive(Debug)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right
-}
-
-#[derive(Debug)]
-struct Instruction {
-    direction: Direction,
-    amount: u32
-}
-
 fn main() {
     let args: Vec<String> = env::args().collect();
     let file_path = args[1].clone();
@@ -23,121 +15,71 @@ fn main() {
 
     println!("Problem 1: {}", problem_1(&contents));
     println!("Problem 2: {}", problem_2(&contents, 10));
-}
 
-fn problem_1(input: &str,) -> u32 {
-    let mut head_pos = (0,0);
-    let mut tail_pos = (0,0);
-    let mut tail_positions = HashSet::<(i32, i32)>::new();
-    tail_positions.insert((0,0));
-
-    for instruction in input.split('\n').map(|l| parse_move(l)) {
-        for _i in 0..instruction.amount {
-            let direction_vector = match instruction.direction {
-                Direction::Up => (0, 1),
-                Direction::Down => (0, -1),
-                Direction::Left => (-1, 0),
-                Direction::Right => (1, 0)
-            };
-
-            head_pos.0 += direction_vector.0;
-            head_pos.1 += direction_vector.1;
-            if is_detached_from_head(head_pos, tail_pos) {
-                tail_pos = head_pos;
-                tail_pos.0 -= direction_vector.0;
-                tail_pos.1 -= direction_vector.1;
-                tail_positions.insert(tail_pos.clone());
-            }
-        }
+    // the options below share the rope described by
+    // [--knots <n>] [--slack <k>] [--attachment <chebyshev|manhattan>] [--track <knot>]
+    let (rope, knot) = configured_rope(&args);
+
+    // day_09 <input> --knots <n> ...
+    if args.iter().any(|a| a == "--knots") {
+        let rope = run_rope(&contents, rope.clone());
+        println!("Knot {} visited {} cells", knot, rope.visited(knot).unwrap().len());
+        println!("Knots end at {:?}", rope.knots());
     }
 
-    return tail_positions.len().try_into().unwrap();
-}
+    // day_09 <input> --visited
+    if args.iter().any(|a| a == "--visited") {
+        let rope = run_rope(&contents, rope.clone());
+        println!("{}", draw_visited(rope.visited(knot).unwrap()));
+    }
 
-fn problem_2(input: &str, tail_length: usize) -> u32 {
-    if input.len() < tail_length {
-        return 0;
+    // day_09 <input> --frames <file> [--window <radius>]
+    if let Some(path) = find_option(&args, "--frames") {
+        let window = find_option(&args, "--window").map(|w| w.parse().expect("Window should be a number"));
+        let instructions = parse_instructions(&contents).unwrap_or_else(|e| panic!("Could not read the moves: {}", e));
+        let mut rope = rope;
+        let mut frames = vec![rope.knots().to_vec()];
+        execute_with(&instructions, &mut rope, |r| frames.push(r.knots().to_vec()));
+        fs::write(path, draw_frames(&frames, window)).unwrap_or_else(|e| panic!("Could not write {}: {}", path, e));
     }
+}
 
-    let mut body_positions = vec![(0,0); tail_length];
-    let mut tail_positions = HashSet::<(i32, i32)>::new();
-    for instruction in input.split('\n').map(|l| parse_move(l)) {
-        for _move_amount in 0..instruction.amount {
-            let direction_vector = match instruction.direction {
-                Direction::Up => (0, 1),
-                Direction::Down => (0, -1),
-                Direction::Left => (-1, 0),
-                Direction::Right => (1, 0)
-            };
-
-            let current_head = body_positions.last().unwrap();
-            let next_head = (current_head.0 + direction_vector.0, current_head.1 + direction_vector.1);
-
-            let neck = body_positions.get(body_positions.len() - 2).unwrap();
-
-            if is_detached_from_head(next_head, *neck) {
-                *body_positions.last_mut().unwrap() = next_head;
-                for x in (0..body_positions.len() - 1).rev() {
-                    body_positions[x] = resolve_next_position(body_positions[x+1], body_positions[x]);
-                }
-            } else {
-                // simply update the current heads position
-                let mut current_head = body_positions.last_mut().unwrap();
-                current_head.0 = next_head.0;
-                current_head.1 = next_head.1;
-            }
-
-            tail_positions.insert(body_positions[0]);
-        }
+fn configured_rope(args: &[String]) -> (Rope, usize) {
+    let knot_count = find_option(args, "--knots").map(|k| k.parse::<usize>().ok().filter(|k| *k > 0).expect("Knots should be a positive number")).unwrap_or(10);
+    let mut rope = Rope::new(knot_count);
+    if let Some(slack) = find_option(args, "--slack") {
+        rope = rope.with_slack(slack.parse().ok().filter(|s| *s > 0).expect("Slack should be a positive number"));
+    }
+    if let Some(attachment) = find_option(args, "--attachment") {
+        rope = rope.with_attachment(Attachment::parse(attachment).unwrap_or_else(|| panic!("Unknown attachment: {}", attachment)));
     }
+    let knot = find_option(args, "--track").map(|k| k.parse::<usize>().ok().filter(|k| *k < knot_count).expect("Tracked knot should be on the rope")).unwrap_or(knot_count - 1);
 
-    return tail_positions.len().try_into().unwrap();
+    (rope.track(knot), knot)
 }
 
-fn parse_move(instruction: &str) -> Instruction {
-    let mut split_instruction = instruction.split(' ');
-    let d = split_instruction.next().unwrap();
-    let a = split_instruction.next().unwrap().parse::<u32>().unwrap();
-
-    let direction = match d {
-        "U" => Direction::Up,
-        "D" => Direction::Down,
-        "L" => Direction::Left,
-        "R" => Direction::Right,
-        _ => panic!()
-    };
-
-    Instruction {
-        direction: direction,
-        amount: a
-    }
+fn find_option<'a>(args: &'a [String], name: &str) -> Option<&'a String> {
+    args.iter().position(|a| a == name).and_then(|i| args.get(i + 1))
 }
 
-fn is_detached_from_head(head: (i32, i32), tail: (i32, i32)) -> bool {
-    let x_dist_abs = (head.0 - tail.0).abs();
-    let y_dist_abs = (head.1 - tail.1).abs();
+fn problem_1(input: &str) -> u32 {
+    knot_visits(input, Rope::new(2), 1)
+}
 
-    x_dist_abs > 1 || y_dist_abs > 1 || (x_dist_abs + y_dist_abs) > 2
+fn problem_2(input: &str, knot_count: usize) -> u32 {
+    knot_visits(input, Rope::new(knot_count), knot_count - 1)
 }
 
-fn resolve_next_position(parent: (i32, i32), child: (i32, i32)) -> (i32, i32) {
-    let x_dis = parent.0 - child.0;
-    let y_dis = parent.1 - child.1;
-
-    let movement = if x_dis.abs() + y_dis.abs() > 2 {
-        // diagonal
-        (1 * (x_dis / x_dis.abs()), 1 * (y_dis / y_dis.abs()))
-    } else if x_dis.abs() > 1 {
-        (1 * (x_dis / x_dis.abs()), 0)
-    } else if y_dis.abs() > 1 {
-        (0, 1 * (y_dis / y_dis.abs()))
-    } else {
-        (0,0)
-    };
-
-    return (child.0 + movement.0, child.1 + movement.1);
+fn knot_visits(input: &str, rope: Rope, knot: usize) -> u32 {
+    let rope = run_rope(input, rope.track(knot));
+    rope.visited(knot).unwrap().len().try_into().unwrap()
 }
 
+fn run_rope(input: &str, mut rope: Rope) -> Rope {
+    let instructions = parse_instructions(input).unwrap_or_else(|e| panic!("Could not read the moves: {}", e));
+    execute(&instructions, &mut rope);
+    rope
+}
 
 #[cfg(test)]
 mod tests {
@@ -153,8 +95,8 @@ R 4
 D 1
 L 5
 R 2";
-        assert_eq!(13, problem_1(&input));
-        assert_eq!(13, problem_2(&input, 2));
+        assert_eq!(13, problem_1(input));
+        assert_eq!(13, problem_2(input, 2));
     }
 
     #[test]
@@ -168,59 +110,6 @@ R 17
 D 10
 L 25
 U 20";
-        assert_eq!(36, problem_2(&input, 10));
-    }
-
-    #[test]
-    fn test_is_detached_from_head() {
-        assert!(!is_detached_from_head((0,0), (0,0)));
-        assert!(!is_detached_from_head((0,1), (0,0)));
-        assert!(!is_detached_from_head((1,0), (0,0)));
-        assert!(!is_detached_from_head((1,0), (0,0)));
-        assert!(!is_detached_from_head((1,1), (0,0)));
-        assert!(!is_detached_from_head((0,0), (1,0)));
-        assert!(!is_detached_from_head((0,0), (1,1)));
-        assert!(!is_detached_from_head((0,-1), (0,0)));
-        assert!(!is_detached_from_head((-1,0), (0,0)));
-        assert!(!is_detached_from_head((-1,1), (0,0)));
-        assert!(!is_detached_from_head((0,0), (0,-1)));
-        assert!(!is_detached_from_head((0,0), (-1,0)));
-        assert!(!is_detached_from_head((0,0), (-1,-1)));
-        assert!(is_detached_from_head((0,2), (0,0)));
-        assert!(is_detached_from_head((0,-2), (0,0)));
-        assert!(is_detached_from_head((2,0), (0,0)));
-        assert!(is_detached_from_head((0,-2), (0,0)));
-        assert!(is_detached_from_head((-2,-2), (0,0)));
-        assert!(is_detached_from_head((2,2), (0,0)));
-        assert!(is_detached_from_head((0,0), (0,2)));
-        assert!(is_detached_from_head((0,0), (0,-2)));
-        assert!(is_detached_from_head((0,0), (2,0)));
-        assert!(is_detached_from_head((0,0), (-2,0)));
-        assert!(is_detached_from_head((0,0), (-2,-2)));
-        assert!(is_detached_from_head((0,0), (2,2)));
+        assert_eq!(36, problem_2(input, 10));
     }
-
-    #[test]
-    fn resolves_next_position() {
-        assert_eq!((0,0), resolve_next_position((0,0), (0,0)));
-        assert_eq!((0,0), resolve_next_position((0,1), (0,0)));
-        assert_eq!((0,0), resolve_next_position((1,0), (0,0)));
-        assert_eq!((0,0), resolve_next_position((1,1), (0,0)));
-        assert_eq!((0,1), resolve_next_position((0,0), (0,1)));
-        assert_eq!((1,0), resolve_next_position((0,0), (1,0)));
-        assert_eq!((1,1), resolve_next_position((0,0), (1,1)));
-        assert_eq!((0,1), resolve_next_position((0,2), (0,0)));
-        assert_eq!((1,0), resolve_next_position((2,0), (0,0)));
-        assert_eq!((1,1), resolve_next_position((2,2), (0,0)));
-        assert_eq!((0,1), resolve_next_position((0,0), (0,2)));
-        assert_eq!((1,0), resolve_next_position((0,0), (2,0)));
-        assert_eq!((1,1), resolve_next_position((0,0), (2,2)));
-        assert_eq!((0,-1), resolve_next_position((0,-2), (0,0)));
-        assert_eq!((-1,0), resolve_next_position((-2,0), (0,0)));
-        assert_eq!((-1,-1), resolve_next_position((-2,-2), (0,0)));
-        assert_eq!((0,-1), resolve_next_position((0,0), (0,-2)));
-        assert_eq!((-1,0), resolve_next_position((0,0), (-2,0)));
-        assert_eq!((-1,-1), resolve_next_position((0,0), (-2,-2)));
-        assert_eq!((4,1), resolve_next_position((4,2), (3,0)));
-    }
-}
\ No newline at end of file
+}
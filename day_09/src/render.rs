@@ -0,0 +1,130 @@
+use std::collections::HashSet;
+
+// The cells a drawing covers, inclusive. y grows upwards, so max_y is the top
+// row.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Bounds {
+	pub min_x: i32,
+	pub max_x: i32,
+	pub min_y: i32,
+	pub max_y: i32
+}
+
+impl Bounds {
+	// Always includes the origin, where every rope starts.
+	pub fn around<'a>(points: impl IntoIterator<Item = &'a (i32, i32)>) -> Bounds {
+		let mut bounds = Bounds { min_x: 0, max_x: 0, min_y: 0, max_y: 0 };
+		for point in points {
+			bounds.min_x = bounds.min_x.min(point.0);
+			bounds.max_x = bounds.max_x.max(point.0);
+			bounds.min_y = bounds.min_y.min(point.1);
+			bounds.max_y = bounds.max_y.max(point.1);
+		}
+
+		bounds
+	}
+
+	pub fn centred_on(point: (i32, i32), radius: i32) -> Bounds {
+		Bounds { min_x: point.0 - radius, max_x: point.0 + radius, min_y: point.1 - radius, max_y: point.1 + radius }
+	}
+
+	fn draw(&self, cell: impl Fn((i32, i32)) -> char) -> String {
+		(self.min_y..=self.max_y)
+			.rev()
+			.map(|y| (self.min_x..=self.max_x).map(|x| cell((x, y))).collect::<String>())
+			.collect::<Vec<String>>()
+			.join("\n")
+	}
+}
+
+// Labels knots the way the puzzle does: H for the head, T for the tail and
+// their index in between. Knots past 9 are drawn as '#'.
+pub fn knot_label(knot: usize, knot_count: usize) -> char {
+	match knot {
+		0 => 'H',
+		k if k + 1 == knot_count => 'T',
+		k => char::from_digit(k as u32, 10).unwrap_or('#')
+	}
+}
+
+// Knots nearer the head are drawn over the ones behind them, and the start is
+// marked 's' when nothing covers it.
+pub fn draw_rope(knots: &[(i32, i32)], bounds: Bounds) -> String {
+	bounds.draw(|cell| match knots.iter().position(|k| *k == cell) {
+		Some(knot) => knot_label(knot, knots.len()),
+		None if cell == (0, 0) => 's',
+		None => '.'
+	})
+}
+
+pub fn draw_visited(visited: &HashSet<(i32, i32)>) -> String {
+	Bounds::around(visited).draw(|cell| match cell {
+		(0, 0) => 's',
+		cell if visited.contains(&cell) => '#',
+		_ => '.'
+	})
+}
+
+// One frame per head step, each under a header, ready to be played back.
+pub fn draw_frames(frames: &[Vec<(i32, i32)>], window: Option<i32>) -> String {
+	let whole = Bounds::around(frames.iter().flatten());
+	frames.iter()
+		.enumerate()
+		.map(|(step, knots)| {
+			let bounds = window.map(|radius| Bounds::centred_on(knots[0], radius)).unwrap_or(whole);
+			format!("== step {} ==\n{}\n", step, draw_rope(knots, bounds))
+		})
+		.collect::<Vec<String>>()
+		.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::instruction::{execute_with, parse_instructions};
+	use crate::rope::Rope;
+
+	#[test]
+	fn draws_like_the_puzzle() {
+		// the first example after R 4, U 4 with ten knots, 6 covering 7 to 9
+		// and the start
+		let mut rope = Rope::new(10);
+		execute_with(&parse_instructions("R 4\nU 4").unwrap(), &mut rope, |_| ());
+		let bounds = Bounds { min_x: 0, max_x: 5, min_y: 0, max_y: 4 };
+		assert_eq!("\
+....H.
+....1.
+..432.
+.5....
+6.....", draw_rope(rope.knots(), bounds));
+	}
+
+	#[test]
+	fn labels_two_knot_rope() {
+		assert_eq!("\
+.H
+sT", draw_rope(&[(1, 1), (1, 0)], Bounds { min_x: 0, max_x: 1, min_y: 0, max_y: 1 }));
+		assert_eq!('#', knot_label(12, 20));
+	}
+
+	#[test]
+	fn draws_visited_cells() {
+		let mut rope = Rope::new(2).track(1);
+		execute_with(&parse_instructions("R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2").unwrap(), &mut rope, |_| ());
+		assert_eq!("\
+..##.
+...##
+.####
+....#
+s###.", draw_visited(rope.visited(1).unwrap()));
+	}
+
+	#[test]
+	fn exports_frames() {
+		let mut rope = Rope::new(2);
+		let mut frames = vec![rope.knots().to_vec()];
+		execute_with(&parse_instructions("R 2").unwrap(), &mut rope, |r| frames.push(r.knots().to_vec()));
+		assert_eq!("== step 0 ==\nH..\n\n== step 1 ==\nTH.\n\n== step 2 ==\nsTH\n", draw_frames(&frames, None));
+		assert_eq!("== step 0 ==\n...\nTH.\n...\n", draw_frames(&frames[2..], Some(1)));
+	}
+}
@@ -0,0 +1,215 @@
+use crate::instruction::Direction;
+use std::collections::HashSet;
+
+// How far apart two knots are for the purpose of staying attached.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Attachment {
+	// touching diagonally counts, and knots catch up diagonally
+	Chebyshev,
+	// only straight-line distance counts, and knots catch up one axis at a time
+	Manhattan
+}
+
+// Knot 0 is the head. Every other knot follows the one in front of it as soon
+// as they are more than `slack` apart.
+#[derive(Clone)]
+pub struct Rope {
+	knots: Vec<(i32, i32)>,
+	slack: i32,
+	attachment: Attachment,
+	visited: Vec<Option<HashSet<(i32, i32)>>>
+}
+
+impl Attachment {
+	pub fn parse(name: &str) -> Option<Attachment> {
+		match name {
+			"chebyshev" => Some(Attachment::Chebyshev),
+			"manhattan" => Some(Attachment::Manhattan),
+			_ => None
+		}
+	}
+}
+
+impl Rope {
+	// The puzzle's rope: knots touching diagonally are still attached.
+	pub fn new(knot_count: usize) -> Rope {
+		assert!(knot_count > 0, "a rope needs at least one knot");
+		Rope { knots: vec![(0, 0); knot_count], slack: 1, attachment: Attachment::Chebyshev, visited: vec![None; knot_count] }
+	}
+
+	pub fn with_slack(mut self, slack: i32) -> Rope {
+		assert!(slack > 0, "knots must be allowed at least one apart");
+		self.slack = slack;
+		self
+	}
+
+	pub fn with_attachment(mut self, attachment: Attachment) -> Rope {
+		self.attachment = attachment;
+		self
+	}
+
+	// Starts recording every cell `knot` occupies, including where it is now.
+	pub fn track(mut self, knot: usize) -> Rope {
+		self.visited[knot] = Some(HashSet::from([self.knots[knot]]));
+		self
+	}
+
+	pub fn knots(&self) -> &[(i32, i32)] {
+		&self.knots
+	}
+
+	// None unless `track` was asked for this knot.
+	pub fn visited(&self, knot: usize) -> Option<&HashSet<(i32, i32)>> {
+		self.visited.get(knot)?.as_ref()
+	}
+
+	// Moves the head one cell and lets the rest of the rope catch up.
+	pub fn step(&mut self, direction: &Direction) -> &[(i32, i32)] {
+		let (dx, dy) = direction.vector();
+		self.knots[0] = (self.knots[0].0 + dx, self.knots[0].1 + dy);
+		for i in 1..self.knots.len() {
+			self.knots[i] = follow(self.knots[i - 1], self.knots[i], self.slack, self.attachment);
+		}
+
+		for (knot, visited) in self.knots.iter().zip(self.visited.iter_mut()) {
+			if let Some(visited) = visited {
+				visited.insert(*knot);
+			}
+		}
+
+		&self.knots
+	}
+}
+
+pub fn is_detached(leader: (i32, i32), follower: (i32, i32), slack: i32, attachment: Attachment) -> bool {
+	let (x, y) = ((leader.0 - follower.0).abs(), (leader.1 - follower.1).abs());
+	let distance = match attachment {
+		Attachment::Chebyshev => x.max(y),
+		Attachment::Manhattan => x + y
+	};
+
+	distance > slack
+}
+
+// Where `follower` ends up once it has caught up with `leader`.
+pub fn follow(leader: (i32, i32), mut follower: (i32, i32), slack: i32, attachment: Attachment) -> (i32, i32) {
+	while is_detached(leader, follower, slack, attachment) {
+		let (x, y) = (leader.0 - follower.0, leader.1 - follower.1);
+		match attachment {
+			Attachment::Chebyshev => follower = (follower.0 + x.signum(), follower.1 + y.signum()),
+			// ties close the horizontal gap first
+			Attachment::Manhattan if x.abs() >= y.abs() => follower.0 += x.signum(),
+			Attachment::Manhattan => follower.1 += y.signum()
+		}
+	}
+
+	follower
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn detached(head: (i32, i32), tail: (i32, i32)) -> bool {
+		is_detached(head, tail, 1, Attachment::Chebyshev)
+	}
+
+	fn next_position(parent: (i32, i32), child: (i32, i32)) -> (i32, i32) {
+		follow(parent, child, 1, Attachment::Chebyshev)
+	}
+
+	#[test]
+	fn test_is_detached_from_head() {
+		assert!(!detached((0,0), (0,0)));
+		assert!(!detached((0,1), (0,0)));
+		assert!(!detached((1,0), (0,0)));
+		assert!(!detached((1,0), (0,0)));
+		assert!(!detached((1,1), (0,0)));
+		assert!(!detached((0,0), (1,0)));
+		assert!(!detached((0,0), (1,1)));
+		assert!(!detached((0,-1), (0,0)));
+		assert!(!detached((-1,0), (0,0)));
+		assert!(!detached((-1,1), (0,0)));
+		assert!(!detached((0,0), (0,-1)));
+		assert!(!detached((0,0), (-1,0)));
+		assert!(!detached((0,0), (-1,-1)));
+		assert!(detached((0,2), (0,0)));
+		assert!(detached((0,-2), (0,0)));
+		assert!(detached((2,0), (0,0)));
+		assert!(detached((0,-2), (0,0)));
+		assert!(detached((-2,-2), (0,0)));
+		assert!(detached((2,2), (0,0)));
+		assert!(detached((0,0), (0,2)));
+		assert!(detached((0,0), (0,-2)));
+		assert!(detached((0,0), (2,0)));
+		assert!(detached((0,0), (-2,0)));
+		assert!(detached((0,0), (-2,-2)));
+		assert!(detached((0,0), (2,2)));
+	}
+
+	#[test]
+	fn resolves_next_position() {
+		assert_eq!((0,0), next_position((0,0), (0,0)));
+		assert_eq!((0,0), next_position((0,1), (0,0)));
+		assert_eq!((0,0), next_position((1,0), (0,0)));
+		assert_eq!((0,0), next_position((1,1), (0,0)));
+		assert_eq!((0,1), next_position((0,0), (0,1)));
+		assert_eq!((1,0), next_position((0,0), (1,0)));
+		assert_eq!((1,1), next_position((0,0), (1,1)));
+		assert_eq!((0,1), next_position((0,2), (0,0)));
+		assert_eq!((1,0), next_position((2,0), (0,0)));
+		assert_eq!((1,1), next_position((2,2), (0,0)));
+		assert_eq!((0,1), next_position((0,0), (0,2)));
+		assert_eq!((1,0), next_position((0,0), (2,0)));
+		assert_eq!((1,1), next_position((0,0), (2,2)));
+		assert_eq!((0,-1), next_position((0,-2), (0,0)));
+		assert_eq!((-1,0), next_position((-2,0), (0,0)));
+		assert_eq!((-1,-1), next_position((-2,-2), (0,0)));
+		assert_eq!((0,-1), next_position((0,0), (0,-2)));
+		assert_eq!((-1,0), next_position((0,0), (-2,0)));
+		assert_eq!((-1,-1), next_position((0,0), (-2,-2)));
+		assert_eq!((4,1), next_position((4,2), (3,0)));
+	}
+
+	#[test]
+	fn exposes_every_knot() {
+		let mut rope = Rope::new(3);
+		rope.step(&Direction::Right);
+		assert_eq!(&[(1, 1), (0, 0), (0, 0)], rope.step(&Direction::Up));
+		assert_eq!(&[(1, 2), (1, 1), (0, 0)], rope.step(&Direction::Up));
+	}
+
+	#[test]
+	fn slack_lets_knots_drift() {
+		let mut rope = Rope::new(2).with_slack(2).track(1);
+		rope.step(&Direction::Right);
+		rope.step(&Direction::Right);
+		assert_eq!((0, 0), rope.knots()[1]);
+		rope.step(&Direction::Right);
+		assert_eq!((1, 0), rope.knots()[1]);
+		assert_eq!(2, rope.visited(1).unwrap().len());
+		assert!(rope.visited(0).is_none());
+	}
+
+	#[test]
+	fn manhattan_knots_follow_straight() {
+		let mut rope = Rope::new(2).with_attachment(Attachment::Manhattan);
+		rope.step(&Direction::Right);
+		rope.step(&Direction::Up);
+		assert_eq!((1, 0), rope.knots()[1]);
+		rope.step(&Direction::Left);
+		assert_eq!((0, 0), rope.knots()[1]);
+		rope.step(&Direction::Left);
+		assert_eq!((-1, 0), rope.knots()[1]);
+	}
+
+	#[test]
+	fn tracks_any_knot() {
+		let mut rope = Rope::new(10).track(0).track(5);
+		for _ in 0..5 {
+			rope.step(&Direction::Up);
+		}
+		assert_eq!(6, rope.visited(0).unwrap().len());
+		assert_eq!(1, rope.visited(5).unwrap().len());
+	}
+}
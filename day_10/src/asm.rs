@@ -0,0 +1,201 @@
+use crate::cpu::{Condition, Instruction, Operand, Register};
+use std::fmt;
+
+// Both counted from 1.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Position {
+	pub line: usize,
+	pub column: usize
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Statement {
+	pub position: Position,
+	pub instruction: Instruction
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Program {
+	pub statements: Vec<Statement>
+}
+
+impl Program {
+	pub fn instructions(&self) -> Vec<Instruction> {
+		self.statements.iter().map(|s| s.instruction).collect()
+	}
+}
+
+// Pretty-prints the program back to source, one instruction per line with
+// single spaces between words.
+impl fmt::Display for Program {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		for statement in &self.statements {
+			writeln!(f, "{}", statement.instruction)?;
+		}
+		Ok(())
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AsmErrorKind {
+	UnknownOpcode(String),
+	MissingOperand,
+	BadRegister(String),
+	BadOperand(String),
+	BadOffset(String),
+	TrailingInput(String),
+	EmptyLine
+}
+
+#[derive(Debug, PartialEq)]
+pub struct AsmError {
+	pub line: usize,
+	pub column: usize,
+	pub text: String,
+	pub kind: AsmErrorKind
+}
+
+impl fmt::Display for AsmError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "line {} column {} '{}': ", self.line, self.column, self.text)?;
+		match &self.kind {
+			AsmErrorKind::UnknownOpcode(opcode) => write!(f, "unknown instruction {}", opcode),
+			AsmErrorKind::MissingOperand => write!(f, "missing operand"),
+			AsmErrorKind::BadRegister(register) => write!(f, "{} is not a register", register),
+			AsmErrorKind::BadOperand(operand) => write!(f, "{} is neither a number nor a register", operand),
+			AsmErrorKind::BadOffset(offset) => write!(f, "{} is not a jump offset", offset),
+			AsmErrorKind::TrailingInput(rest) => write!(f, "unexpected {}", rest),
+			AsmErrorKind::EmptyLine => write!(f, "no instruction")
+		}
+	}
+}
+
+// Blank lines are skipped but still counted, so positions match the file.
+pub fn parse_program(input: &str) -> Result<Program, AsmError> {
+	let mut statements = vec![];
+	for (line_number, line) in input.lines().enumerate() {
+		let Some((column, _)) = words(line).first().copied() else {
+			continue;
+		};
+
+		let instruction = parse_instruction(line).map_err(|e| AsmError { line: line_number + 1, ..e })?;
+		statements.push(Statement { position: Position { line: line_number + 1, column }, instruction });
+	}
+
+	Ok(Program { statements })
+}
+
+// One instruction, reported as if it were the first line of a program:
+//   noop, halt                        one cycle each
+//   add<r>/mul<r>/set<r> <value|reg>  e.g. addx 3, mula b, setx 0
+//   jmp <offset>                      relative to the jump
+//   jz/jnz/jgz/jlz <reg> <offset>     jumps when the register is 0, not 0, >0, <0
+pub fn parse_instruction(text: &str) -> Result<Instruction, AsmError> {
+	parse_words(&words(text)).map_err(|(column, kind)| AsmError { line: 1, column, text: text.to_string(), kind })
+}
+
+fn words(line: &str) -> Vec<(usize, &str)> {
+	let mut words = vec![];
+	let mut start = None;
+	for (i, c) in line.char_indices().chain([(line.len(), ' ')]) {
+		match (start, c.is_whitespace()) {
+			(None, false) => start = Some(i),
+			(Some(s), true) => {
+				words.push((s + 1, &line[s..i]));
+				start = None;
+			},
+			_ => ()
+		}
+	}
+
+	words
+}
+
+type Arithmetic = fn(Register, Operand) -> Instruction;
+
+fn arithmetic(mnemonic: &str) -> Option<(Arithmetic, Register)> {
+	let register = Register::parse(mnemonic.get(3..)?)?;
+	let build: Arithmetic = match mnemonic.get(..3)? {
+		"add" => |register, operand| Instruction::Add { register, operand },
+		"mul" => |register, operand| Instruction::Mul { register, operand },
+		"set" => |register, operand| Instruction::Set { register, operand },
+		_ => return None
+	};
+
+	Some((build, register))
+}
+
+fn parse_words(words: &[(usize, &str)]) -> Result<Instruction, (usize, AsmErrorKind)> {
+	let (column, mnemonic) = *words.first().ok_or((1, AsmErrorKind::EmptyLine))?;
+	let operand_count = match mnemonic {
+		"noop" | "halt" => 0,
+		"jmp" => 1,
+		m if Condition::parse(m).is_some() => 2,
+		m if arithmetic(m).is_some() => 1,
+		_ => return Err((column, AsmErrorKind::UnknownOpcode(mnemonic.to_string())))
+	};
+	if let Some((column, extra)) = words.get(operand_count + 1) {
+		return Err((*column, AsmErrorKind::TrailingInput(extra.to_string())));
+	}
+	if words.len() <= operand_count {
+		let (column, last) = words[words.len() - 1];
+		return Err((column + last.len(), AsmErrorKind::MissingOperand));
+	}
+
+	let offset = |(column, word): (usize, &str)| word.parse::<i32>().map_err(|_| (column, AsmErrorKind::BadOffset(word.to_string())));
+	let register = |(column, word): (usize, &str)| Register::parse(word).ok_or((column, AsmErrorKind::BadRegister(word.to_string())));
+	let operand = |(column, word): (usize, &str)| Operand::parse(word).ok_or((column, AsmErrorKind::BadOperand(word.to_string())));
+
+	Ok(match mnemonic {
+		"noop" => Instruction::Noop,
+		"halt" => Instruction::Halt,
+		"jmp" => Instruction::Jump(offset(words[1])?),
+		_ => match Condition::parse(mnemonic) {
+			Some(condition) => Instruction::Branch { condition, register: register(words[1])?, offset: offset(words[2])? },
+			None => {
+				let (build, register) = arithmetic(mnemonic).unwrap();
+				build(register, operand(words[1])?)
+			}
+		}
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn error(text: &str) -> (usize, AsmErrorKind) {
+		let error = parse_instruction(text).unwrap_err();
+		(error.column, error.kind)
+	}
+
+	#[test]
+	fn keeps_source_positions() {
+		let program = parse_program("noop\n\n  addx -3\n\tjnz x -1\n").unwrap();
+		let positions = program.statements.iter().map(|s| s.position).collect::<Vec<Position>>();
+		assert_eq!(vec![Position { line: 1, column: 1 }, Position { line: 3, column: 3 }, Position { line: 4, column: 2 }], positions);
+		assert_eq!(Instruction::Branch { condition: Condition::NotZero, register: Register::X, offset: -1 }, program.statements[2].instruction);
+	}
+
+	#[test]
+	fn pretty_prints_back_to_source() {
+		let source = "  addx   15\nmula x\n\nseta -2\njgz  a 3\njmp -1\nhalt\n";
+		let program = parse_program(source).unwrap();
+		assert_eq!("addx 15\nmula x\nseta -2\njgz a 3\njmp -1\nhalt\n", program.to_string());
+		assert_eq!(program.instructions(), parse_program(&program.to_string()).unwrap().instructions());
+	}
+
+	#[test]
+	fn reports_where_it_went_wrong() {
+		assert_eq!((3, AsmErrorKind::UnknownOpcode("subx".to_string())), error("  subx 1"));
+		assert_eq!((5, AsmErrorKind::MissingOperand), error("addq"));
+		assert_eq!((6, AsmErrorKind::BadOperand("ten".to_string())), error("addx ten"));
+		assert_eq!((5, AsmErrorKind::BadRegister("xy".to_string())), error("jnz xy 2"));
+		assert_eq!((7, AsmErrorKind::BadOffset("x".to_string())), error("jz x  x"));
+		assert_eq!((8, AsmErrorKind::TrailingInput("2".to_string())), error("addx 1 2"));
+
+		let error = parse_program("noop\naddx 1\nnope").unwrap_err();
+		assert_eq!(3, error.line);
+		assert_eq!("line 3 column 1 'nope': unknown instruction nope", error.to_string());
+	}
+}
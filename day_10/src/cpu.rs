@@ -0,0 +1,405 @@
+use std::fmt;
+
+// Programs that jump backwards can run forever, so the clock gives up after
+// this many cycles unless told otherwise.
+pub const DEFAULT_CYCLE_LIMIT: u64 = 1_000_000;
+
+// One of the registers a to z. The puzzle only ever uses x.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Register(u8);
+
+impl Register {
+	pub const X: Register = Register(b'x' - b'a');
+
+	pub fn parse(name: &str) -> Option<Register> {
+		match name.as_bytes() {
+			[c] if c.is_ascii_lowercase() => Some(Register(c - b'a')),
+			_ => None
+		}
+	}
+}
+
+impl fmt::Display for Register {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", (b'a' + self.0) as char)
+	}
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Registers([i32; 26]);
+
+impl Registers {
+	// x starts at 1, everything else at 0
+	pub fn new() -> Registers {
+		let mut registers = Registers([0; 26]);
+		registers.set(Register::X, 1);
+		registers
+	}
+
+	pub fn get(&self, register: Register) -> i32 {
+		self.0[register.0 as usize]
+	}
+
+	pub fn x(&self) -> i32 {
+		self.get(Register::X)
+	}
+
+	fn set(&mut self, register: Register, value: i32) {
+		self.0[register.0 as usize] = value;
+	}
+}
+
+// Only the registers that are not 0, in alphabetical order.
+impl fmt::Display for Registers {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let set = (0..26u8)
+			.filter(|r| self.0[*r as usize] != 0)
+			.map(|r| format!("{}={}", Register(r), self.0[r as usize]))
+			.collect::<Vec<String>>();
+		write!(f, "{}", set.join(" "))
+	}
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Operand {
+	Value(i32),
+	Register(Register)
+}
+
+impl Operand {
+	pub fn parse(text: &str) -> Option<Operand> {
+		text.parse().ok().map(Operand::Value).or_else(|| Register::parse(text).map(Operand::Register))
+	}
+
+	fn read(&self, registers: &Registers) -> i32 {
+		match self {
+			Operand::Value(value) => *value,
+			Operand::Register(register) => registers.get(*register)
+		}
+	}
+}
+
+impl fmt::Display for Operand {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Operand::Value(value) => write!(f, "{}", value),
+			Operand::Register(register) => write!(f, "{}", register)
+		}
+	}
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Condition {
+	Zero,
+	NotZero,
+	Positive,
+	Negative
+}
+
+impl Condition {
+	pub fn parse(mnemonic: &str) -> Option<Condition> {
+		match mnemonic {
+			"jz" => Some(Condition::Zero),
+			"jnz" => Some(Condition::NotZero),
+			"jgz" => Some(Condition::Positive),
+			"jlz" => Some(Condition::Negative),
+			_ => None
+		}
+	}
+
+	pub fn mnemonic(&self) -> &'static str {
+		match self {
+			Condition::Zero => "jz",
+			Condition::NotZero => "jnz",
+			Condition::Positive => "jgz",
+			Condition::Negative => "jlz"
+		}
+	}
+
+	fn holds(&self, value: i32) -> bool {
+		match self {
+			Condition::Zero => value == 0,
+			Condition::NotZero => value != 0,
+			Condition::Positive => value > 0,
+			Condition::Negative => value < 0
+		}
+	}
+}
+
+// Jump offsets are relative to the jump itself, so `jmp 0` spins forever and
+// `jmp 1` does nothing.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Instruction {
+	Noop,
+	Add { register: Register, operand: Operand },
+	Mul { register: Register, operand: Operand },
+	Set { register: Register, operand: Operand },
+	Jump(i32),
+	Branch { condition: Condition, register: Register, offset: i32 },
+	Halt
+}
+
+impl Instruction {
+	// Registers only change once the last of these cycles is over.
+	pub fn cycles(&self) -> u32 {
+		match self {
+			Instruction::Noop => 1,
+			Instruction::Add { .. } => 2,
+			Instruction::Mul { .. } => 3,
+			Instruction::Set { .. } => 1,
+			Instruction::Jump(_) => 2,
+			Instruction::Branch { .. } => 2,
+			Instruction::Halt => 1
+		}
+	}
+}
+
+impl fmt::Display for Instruction {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Instruction::Noop => write!(f, "noop"),
+			Instruction::Add { register, operand } => write!(f, "add{} {}", register, operand),
+			Instruction::Mul { register, operand } => write!(f, "mul{} {}", register, operand),
+			Instruction::Set { register, operand } => write!(f, "set{} {}", register, operand),
+			Instruction::Jump(offset) => write!(f, "jmp {}", offset),
+			Instruction::Branch { condition, register, offset } => write!(f, "{} {} {}", condition.mnemonic(), register, offset),
+			Instruction::Halt => write!(f, "halt")
+		}
+	}
+}
+
+// What the CPU looked like during one clock cycle, before the instruction at
+// `pc` has had any effect.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Tick {
+	// counted from 1, like the puzzle does
+	pub cycle: u64,
+	pub pc: usize,
+	pub instruction: Instruction,
+	pub registers: Registers
+}
+
+pub trait ClockListener {
+	fn on_tick(&mut self, tick: &Tick);
+}
+
+impl<F: FnMut(&Tick)> ClockListener for F {
+	fn on_tick(&mut self, tick: &Tick) {
+		self(tick)
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CpuError {
+	CycleLimit(u64)
+}
+
+impl fmt::Display for CpuError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			CpuError::CycleLimit(limit) => write!(f, "program did not finish within {} cycles", limit)
+		}
+	}
+}
+
+// The program finishes when it halts or runs off either end.
+pub struct Cpu {
+	program: Vec<Instruction>,
+	registers: Registers,
+	pc: usize,
+	cycle: u64,
+	// cycles the instruction at `pc` still needs, 0 when it has not started
+	busy: u32,
+	halted: bool,
+	cycle_limit: u64
+}
+
+impl Cpu {
+	pub fn new(program: Vec<Instruction>) -> Cpu {
+		Cpu { program, registers: Registers::new(), pc: 0, cycle: 0, busy: 0, halted: false, cycle_limit: DEFAULT_CYCLE_LIMIT }
+	}
+
+	pub fn with_cycle_limit(mut self, cycle_limit: u64) -> Cpu {
+		self.cycle_limit = cycle_limit;
+		self
+	}
+
+	pub fn registers(&self) -> &Registers {
+		&self.registers
+	}
+
+	pub fn pc(&self) -> usize {
+		self.pc
+	}
+
+	// Cycles completed so far.
+	pub fn cycle(&self) -> u64 {
+		self.cycle
+	}
+
+	// True between the cycles of an instruction that takes more than one.
+	pub fn in_progress(&self) -> bool {
+		self.busy > 0
+	}
+
+	pub fn is_finished(&self) -> bool {
+		self.halted || self.pc >= self.program.len()
+	}
+
+	pub fn hit_cycle_limit(&self) -> bool {
+		!self.is_finished() && self.cycle >= self.cycle_limit
+	}
+
+	// Runs a single clock cycle, or returns None once the program is over.
+	pub fn tick(&mut self) -> Option<Tick> {
+		if self.is_finished() {
+			return None;
+		}
+
+		let instruction = self.program[self.pc];
+		if self.busy == 0 {
+			self.busy = instruction.cycles();
+		}
+		self.cycle += 1;
+		let tick = Tick { cycle: self.cycle, pc: self.pc, instruction, registers: self.registers };
+
+		self.busy -= 1;
+		if self.busy == 0 {
+			self.retire(instruction);
+		}
+
+		Some(tick)
+	}
+
+	// Drives the clock until the program is over, telling every subscriber
+	// about every cycle. Returns the number of cycles run.
+	pub fn run(&mut self, subscribers: &mut [&mut dyn ClockListener]) -> Result<u64, CpuError> {
+		while !self.is_finished() {
+			if self.hit_cycle_limit() {
+				return Err(CpuError::CycleLimit(self.cycle_limit));
+			}
+
+			let tick = self.tick().unwrap();
+			for subscriber in subscribers.iter_mut() {
+				subscriber.on_tick(&tick);
+			}
+		}
+
+		Ok(self.cycle)
+	}
+
+	fn retire(&mut self, instruction: Instruction) {
+		let registers = &mut self.registers;
+		let offset = match instruction {
+			Instruction::Noop => 1,
+			Instruction::Add { register, operand } => {
+				registers.set(register, registers.get(register).wrapping_add(operand.read(registers)));
+				1
+			},
+			Instruction::Mul { register, operand } => {
+				registers.set(register, registers.get(register).wrapping_mul(operand.read(registers)));
+				1
+			},
+			Instruction::Set { register, operand } => {
+				registers.set(register, operand.read(registers));
+				1
+			},
+			Instruction::Jump(offset) => offset,
+			Instruction::Branch { condition, register, offset } => if condition.holds(registers.get(register)) { offset } else { 1 },
+			Instruction::Halt => {
+				self.halted = true;
+				0
+			}
+		};
+
+		// jumping before the start ends the program just like running off the end
+		match usize::try_from(self.pc as i64 + offset as i64) {
+			Ok(pc) => self.pc = pc,
+			Err(_) => self.halted = true
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn register(name: &str) -> Register {
+		Register::parse(name).unwrap()
+	}
+
+	fn trace(program: Vec<Instruction>) -> Vec<(u64, usize, i32)> {
+		let mut ticks = vec![];
+		Cpu::new(program).run(&mut [&mut |t: &Tick| ticks.push((t.cycle, t.pc, t.registers.x()))]).unwrap();
+		ticks
+	}
+
+	#[test]
+	fn runs_the_small_example() {
+		let program = vec![
+			Instruction::Noop,
+			Instruction::Add { register: Register::X, operand: Operand::Value(3) },
+			Instruction::Add { register: Register::X, operand: Operand::Value(-5) }
+		];
+		let mut cpu = Cpu::new(program);
+		let mut xs = vec![];
+		assert_eq!(Ok(5), cpu.run(&mut [&mut |t: &Tick| xs.push(t.registers.x())]));
+		assert_eq!(vec![1, 1, 1, 4, 4], xs);
+		assert_eq!(-1, cpu.registers().x());
+	}
+
+	#[test]
+	fn instructions_take_their_cycle_cost() {
+		let program = vec![
+			Instruction::Mul { register: Register::X, operand: Operand::Value(4) },
+			Instruction::Set { register: register("a"), operand: Operand::Register(Register::X) },
+			Instruction::Halt,
+			Instruction::Noop
+		];
+		assert_eq!(vec![(1, 0, 1), (2, 0, 1), (3, 0, 1), (4, 1, 4), (5, 2, 4)], trace(program.clone()));
+
+		let mut cpu = Cpu::new(program);
+		cpu.tick();
+		assert!(cpu.in_progress());
+		cpu.run(&mut []).unwrap();
+		assert_eq!(2, cpu.pc());
+		assert_eq!(4, cpu.registers().get(register("a")));
+		assert_eq!("a=4 x=4", cpu.registers().to_string());
+	}
+
+	#[test]
+	fn branches_loop_until_the_register_is_zero() {
+		// counts a down from 3, adding 2 to x each time round
+		let program = vec![
+			Instruction::Set { register: register("a"), operand: Operand::Value(3) },
+			Instruction::Add { register: Register::X, operand: Operand::Value(2) },
+			Instruction::Add { register: register("a"), operand: Operand::Value(-1) },
+			Instruction::Branch { condition: Condition::NotZero, register: register("a"), offset: -2 },
+			Instruction::Jump(2),
+			Instruction::Halt
+		];
+		let mut cpu = Cpu::new(program);
+		// 1 + 3 * (2 + 2 + 2) + 2
+		assert_eq!(Ok(21), cpu.run(&mut []));
+		assert_eq!(7, cpu.registers().x());
+		assert!(cpu.is_finished());
+	}
+
+	#[test]
+	fn gives_up_on_endless_loops() {
+		let mut cpu = Cpu::new(vec![Instruction::Jump(0)]).with_cycle_limit(10);
+		assert_eq!(Err(CpuError::CycleLimit(10)), cpu.run(&mut []));
+		assert_eq!(10, cpu.cycle());
+		assert!(cpu.hit_cycle_limit());
+	}
+
+	#[test]
+	fn every_subscriber_sees_every_cycle() {
+		let (mut first, mut second) = (0, 0);
+		let program = vec![Instruction::Noop, Instruction::Jump(-5)];
+		let mut cpu = Cpu::new(program);
+		assert_eq!(Ok(3), cpu.run(&mut [&mut |_: &Tick| first += 1, &mut |_: &Tick| second += 1]));
+		assert_eq!((3, 3), (first, second));
+		assert!(cpu.is_finished());
+	}
+}
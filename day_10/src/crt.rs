@@ -0,0 +1,80 @@
+use crate::cpu::{ClockListener, Tick};
+
+pub const WIDTH: usize = 40;
+
+// Draws one pixel per clock cycle, left to right and starting a new row every
+// WIDTH pixels.
+pub struct Crt {
+	pixels: Vec<bool>
+}
+
+impl Crt {
+	pub fn new() -> Crt {
+		Crt { pixels: vec![] }
+	}
+
+	// The screen so far, padded with blanks to `rows` rows for the pixels the
+	// beam has not reached yet.
+	pub fn render_partial(&self, rows: usize) -> String {
+		let drawn = self.render();
+		let mut lines = drawn.lines().map(|l| format!("{:<1$}", l, WIDTH)).collect::<Vec<String>>();
+		lines.resize(lines.len().max(rows), " ".repeat(WIDTH));
+		lines.join("\n")
+	}
+
+	pub fn render(&self) -> String {
+		self.pixels
+			.chunks(WIDTH)
+			.map(|row| row.iter().map(|lit| if *lit { '#' } else { '.' }).collect::<String>())
+			.collect::<Vec<String>>()
+			.join("\n")
+	}
+}
+
+impl ClockListener for Crt {
+	fn on_tick(&mut self, tick: &Tick) {
+		self.pixels.push(sprite_covers(column(tick.cycle), tick.registers.x()));
+	}
+}
+
+// The column drawn during a cycle, which is counted from 1.
+pub fn column(cycle: u64) -> usize {
+	(cycle - 1) as usize % WIDTH
+}
+
+// The sprite is three pixels wide and centred on x.
+pub fn sprite_covers(column: usize, x: i32) -> bool {
+	(column as i64 - x as i64).abs() <= 1
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::cpu::{Cpu, Instruction, Operand, Register};
+
+	#[test]
+	fn draws_where_the_sprite_is() {
+		let program = vec![
+			Instruction::Add { register: Register::X, operand: Operand::Value(15) },
+			Instruction::Add { register: Register::X, operand: Operand::Value(-11) },
+			Instruction::Add { register: Register::X, operand: Operand::Value(6) }
+		];
+		let mut crt = Crt::new();
+		Cpu::new(program).run(&mut [&mut crt]).unwrap();
+		assert_eq!("##..##", crt.render());
+	}
+
+	#[test]
+	fn pads_a_partial_screen() {
+		let mut crt = Crt::new();
+		Cpu::new(vec![Instruction::Noop, Instruction::Noop]).run(&mut [&mut crt]).unwrap();
+		assert_eq!(format!("##{}\n{}", " ".repeat(38), " ".repeat(40)), crt.render_partial(2));
+	}
+
+	#[test]
+	fn sprite_can_hang_off_the_edge() {
+		assert!(sprite_covers(0, -1));
+		assert!(!sprite_covers(0, -2));
+		assert!(sprite_covers(39, 40));
+	}
+}
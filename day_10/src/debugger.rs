@@ -0,0 +1,419 @@
+use crate::asm::Program;
+use crate::cpu::{ClockListener, Cpu};
+use crate::crt::Crt;
+use std::fmt;
+
+// How many screen rows the live view always shows, drawn or not.
+const SCREEN_ROWS: usize = 6;
+// Instructions shown either side of the program counter by `list`.
+const LIST_CONTEXT: usize = 5;
+
+// Both stop the program just before the cycle or instruction runs.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Breakpoint {
+	Cycle(u64),
+	Instruction(usize)
+}
+
+impl fmt::Display for Breakpoint {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Breakpoint::Cycle(cycle) => write!(f, "cycle {}", cycle),
+			Breakpoint::Instruction(index) => write!(f, "instruction {}", index)
+		}
+	}
+}
+
+// Both stop the program just after x is written.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Watchpoint {
+	Changes,
+	Becomes(i32)
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Stop {
+	Stepped,
+	Breakpoint(Breakpoint),
+	Watchpoint { from: i32, to: i32 },
+	Finished,
+	CycleLimit
+}
+
+impl fmt::Display for Stop {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Stop::Stepped => Ok(()),
+			Stop::Breakpoint(breakpoint) => write!(f, "stopped at {}", breakpoint),
+			Stop::Watchpoint { from, to } => write!(f, "x changed from {} to {}", from, to),
+			Stop::Finished => write!(f, "program finished"),
+			Stop::CycleLimit => write!(f, "gave up at the cycle limit")
+		}
+	}
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DebugCommand {
+	// by clock cycles
+	Step(u64),
+	// by whole instructions, finishing the current one first
+	Next(u64),
+	Continue,
+	Break(Breakpoint),
+	Delete(Breakpoint),
+	Watch(Watchpoint),
+	Unwatch,
+	Screen,
+	Registers,
+	List
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DebugCommandError {
+	UnknownCommand(String),
+	MissingArgument,
+	BadArgument(String)
+}
+
+impl fmt::Display for DebugCommandError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			DebugCommandError::UnknownCommand(command) => write!(f, "unknown command {}", command),
+			DebugCommandError::MissingArgument => write!(f, "missing argument"),
+			DebugCommandError::BadArgument(argument) => write!(f, "did not understand {}", argument)
+		}
+	}
+}
+
+//   step/s [n], next/n [n], continue/c
+//   break/b cycle <n> | pc <index>, delete/d cycle <n> | pc <index>
+//   watch/w [value], unwatch
+//   screen, registers/r, list/l
+pub fn parse_debug_command(line: &str) -> Result<DebugCommand, DebugCommandError> {
+	let words = line.split_whitespace().collect::<Vec<&str>>();
+	let number = |word: &str| word.parse().map_err(|_| DebugCommandError::BadArgument(word.to_string()));
+	let count = |words: &[&str]| words.first().map_or(Ok(1), |w| number(w));
+	let breakpoint = |words: &[&str]| match words {
+		["cycle", cycle] => Ok(Breakpoint::Cycle(number(cycle)?)),
+		["pc" | "instruction", index] => Ok(Breakpoint::Instruction(number(index)? as usize)),
+		[] | [_] => Err(DebugCommandError::MissingArgument),
+		_ => Err(DebugCommandError::BadArgument(words.join(" ")))
+	};
+
+	match words.split_first() {
+		Some((&("step" | "s"), rest)) => Ok(DebugCommand::Step(count(rest)?)),
+		Some((&("next" | "n"), rest)) => Ok(DebugCommand::Next(count(rest)?)),
+		Some((&("continue" | "c"), [])) => Ok(DebugCommand::Continue),
+		Some((&("break" | "b"), rest)) => Ok(DebugCommand::Break(breakpoint(rest)?)),
+		Some((&("delete" | "d"), rest)) => Ok(DebugCommand::Delete(breakpoint(rest)?)),
+		Some((&("watch" | "w"), [])) => Ok(DebugCommand::Watch(Watchpoint::Changes)),
+		Some((&("watch" | "w"), [value])) => {
+			let value = value.parse().map_err(|_| DebugCommandError::BadArgument(value.to_string()))?;
+			Ok(DebugCommand::Watch(Watchpoint::Becomes(value)))
+		},
+		Some((&"unwatch", [])) => Ok(DebugCommand::Unwatch),
+		Some((&"screen", [])) => Ok(DebugCommand::Screen),
+		Some((&("registers" | "r"), [])) => Ok(DebugCommand::Registers),
+		Some((&("list" | "l"), [])) => Ok(DebugCommand::List),
+		Some((command, _)) => Err(DebugCommandError::UnknownCommand(command.to_string())),
+		None => Err(DebugCommandError::MissingArgument)
+	}
+}
+
+// Runs a program a cycle at a time, drawing the CRT as it goes.
+pub struct Debugger {
+	program: Program,
+	cpu: Cpu,
+	crt: Crt,
+	breakpoints: Vec<Breakpoint>,
+	watchpoints: Vec<Watchpoint>
+}
+
+impl Debugger {
+	pub fn new(program: Program) -> Debugger {
+		let cpu = Cpu::new(program.instructions());
+		Debugger { program, cpu, crt: Crt::new(), breakpoints: vec![], watchpoints: vec![] }
+	}
+
+	pub fn with_cycle_limit(mut self, cycle_limit: u64) -> Debugger {
+		self.cpu = self.cpu.with_cycle_limit(cycle_limit);
+		self
+	}
+
+	pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+		if !self.breakpoints.contains(&breakpoint) {
+			self.breakpoints.push(breakpoint);
+		}
+	}
+
+	// False when there was no such breakpoint.
+	pub fn remove_breakpoint(&mut self, breakpoint: Breakpoint) -> bool {
+		let before = self.breakpoints.len();
+		self.breakpoints.retain(|b| *b != breakpoint);
+		self.breakpoints.len() != before
+	}
+
+	pub fn watch(&mut self, watchpoint: Watchpoint) {
+		if !self.watchpoints.contains(&watchpoint) {
+			self.watchpoints.push(watchpoint);
+		}
+	}
+
+	pub fn unwatch(&mut self) {
+		self.watchpoints.clear();
+	}
+
+	// Asking for 0 steps takes one, like asking for 1.
+	pub fn step_cycles(&mut self, cycles: u64) -> Stop {
+		let mut left = cycles;
+		self.advance(|_| {
+			left = left.saturating_sub(1);
+			left == 0
+		})
+	}
+
+	pub fn step_instructions(&mut self, instructions: u64) -> Stop {
+		let mut left = instructions;
+		self.advance(|cpu| {
+			if !cpu.in_progress() {
+				left = left.saturating_sub(1);
+			}
+			left == 0
+		})
+	}
+
+	pub fn resume(&mut self) -> Stop {
+		self.advance(|_| false)
+	}
+
+	// Runs cycles until `done` says so, the program ends or a breakpoint or
+	// watchpoint is hit. Breakpoints are only checked once the first cycle is
+	// out of the way, so resuming from one moves on.
+	fn advance(&mut self, mut done: impl FnMut(&Cpu) -> bool) -> Stop {
+		if self.cpu.is_finished() {
+			return Stop::Finished;
+		}
+
+		let mut first = true;
+		loop {
+			if self.cpu.hit_cycle_limit() {
+				return Stop::CycleLimit;
+			}
+			if !first {
+				if let Some(breakpoint) = self.breakpoints.iter().find(|b| self.is_at(b)) {
+					return Stop::Breakpoint(*breakpoint);
+				}
+			}
+			first = false;
+
+			let from = self.cpu.registers().x();
+			let tick = self.cpu.tick().unwrap();
+			self.crt.on_tick(&tick);
+			let to = self.cpu.registers().x();
+			if self.watchpoints.iter().any(|w| triggers(w, from, to)) {
+				return Stop::Watchpoint { from, to };
+			}
+
+			if self.cpu.is_finished() {
+				return Stop::Finished;
+			}
+			if done(&self.cpu) {
+				return Stop::Stepped;
+			}
+		}
+	}
+
+	fn is_at(&self, breakpoint: &Breakpoint) -> bool {
+		match breakpoint {
+			Breakpoint::Cycle(cycle) => self.cpu.cycle() + 1 == *cycle,
+			Breakpoint::Instruction(index) => self.cpu.pc() == *index && !self.cpu.in_progress()
+		}
+	}
+
+	// The CRT as drawn so far.
+	pub fn screen(&self) -> String {
+		self.crt.render_partial(SCREEN_ROWS)
+	}
+
+	pub fn status(&self) -> String {
+		if self.cpu.is_finished() {
+			return format!("finished after {} cycles with {}", self.cpu.cycle(), self.cpu.registers());
+		}
+
+		let statement = &self.program.statements[self.cpu.pc()];
+		format!(
+			"cycle {} done, pc {} '{}' on line {}{}, {}",
+			self.cpu.cycle(),
+			self.cpu.pc(),
+			statement.instruction,
+			statement.position.line,
+			if self.cpu.in_progress() { " (part done)" } else { "" },
+			self.cpu.registers()
+		)
+	}
+
+	// The instructions around the program counter, marked '>', with '*' on
+	// instruction breakpoints.
+	pub fn listing(&self) -> String {
+		let pc = self.cpu.pc();
+		let end = (pc + LIST_CONTEXT + 1).min(self.program.statements.len());
+		(pc.saturating_sub(LIST_CONTEXT)..end)
+			.map(|index| {
+				let statement = &self.program.statements[index];
+				format!(
+					"{}{} {:>4}  {:<12} line {}",
+					if index == pc { '>' } else { ' ' },
+					if self.breakpoints.contains(&Breakpoint::Instruction(index)) { '*' } else { ' ' },
+					index,
+					statement.instruction.to_string(),
+					statement.position.line
+				)
+			})
+			.collect::<Vec<String>>()
+			.join("\n")
+	}
+
+	pub fn execute(&mut self, command: &DebugCommand) -> String {
+		let stop = match command {
+			DebugCommand::Step(cycles) => self.step_cycles(*cycles),
+			DebugCommand::Next(instructions) => self.step_instructions(*instructions),
+			DebugCommand::Continue => self.resume(),
+			DebugCommand::Break(breakpoint) => {
+				self.add_breakpoint(*breakpoint);
+				return format!("breakpoint at {}", breakpoint);
+			},
+			DebugCommand::Delete(breakpoint) => {
+				return if self.remove_breakpoint(*breakpoint) { format!("deleted breakpoint at {}", breakpoint) } else { format!("no breakpoint at {}", breakpoint) };
+			},
+			DebugCommand::Watch(watchpoint) => {
+				self.watch(*watchpoint);
+				return match watchpoint {
+					Watchpoint::Changes => "watching x for changes".to_string(),
+					Watchpoint::Becomes(value) => format!("watching x for {}", value)
+				};
+			},
+			DebugCommand::Unwatch => {
+				self.unwatch();
+				return "no longer watching x".to_string();
+			},
+			DebugCommand::Screen => return self.screen(),
+			DebugCommand::Registers => return self.status(),
+			DebugCommand::List => return self.listing()
+		};
+
+		match stop {
+			Stop::Stepped => format!("{}\n{}", self.status(), self.screen()),
+			stop => format!("{}\n{}\n{}", stop, self.status(), self.screen())
+		}
+	}
+}
+
+fn triggers(watchpoint: &Watchpoint, from: i32, to: i32) -> bool {
+	match watchpoint {
+		Watchpoint::Changes => from != to,
+		Watchpoint::Becomes(value) => from != to && to == *value
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::asm::parse_program;
+
+	fn debugger(source: &str) -> Debugger {
+		Debugger::new(parse_program(source).unwrap())
+	}
+
+	#[test]
+	fn steps_by_cycle_and_by_instruction() {
+		let mut debugger = debugger("addx 3\nnoop\naddx -5\nnoop");
+		assert_eq!(Stop::Stepped, debugger.step_cycles(1));
+		assert!(debugger.cpu.in_progress());
+		assert_eq!(1, debugger.cpu.registers().x());
+		// finishes the addx that is under way, then runs the noop
+		assert_eq!(Stop::Stepped, debugger.step_instructions(2));
+		assert_eq!((3, 2, 4), (debugger.cpu.cycle(), debugger.cpu.pc(), debugger.cpu.registers().x()));
+		assert_eq!(Stop::Finished, debugger.step_instructions(5));
+		assert_eq!(Stop::Finished, debugger.step_cycles(1));
+		assert_eq!(6, debugger.cpu.cycle());
+	}
+
+	#[test]
+	fn stops_at_breakpoints() {
+		let mut debugger = debugger("noop\naddx 3\nnoop\nnoop\nnoop");
+		debugger.add_breakpoint(Breakpoint::Cycle(4));
+		debugger.add_breakpoint(Breakpoint::Instruction(3));
+		assert_eq!(Stop::Breakpoint(Breakpoint::Cycle(4)), debugger.resume());
+		assert_eq!((3, 2), (debugger.cpu.cycle(), debugger.cpu.pc()));
+		assert_eq!(Stop::Breakpoint(Breakpoint::Instruction(3)), debugger.resume());
+		assert_eq!(4, debugger.cpu.cycle());
+
+		assert!(debugger.remove_breakpoint(Breakpoint::Cycle(4)));
+		assert!(!debugger.remove_breakpoint(Breakpoint::Cycle(4)));
+		assert_eq!(Stop::Finished, debugger.resume());
+	}
+
+	#[test]
+	fn stops_when_x_is_written() {
+		let mut debugger = debugger("addx 0\naddx 3\nnoop\naddx 2\naddx -4");
+		debugger.watch(Watchpoint::Changes);
+		assert_eq!(Stop::Watchpoint { from: 1, to: 4 }, debugger.resume());
+		assert_eq!(4, debugger.cpu.cycle());
+
+		debugger.unwatch();
+		debugger.watch(Watchpoint::Becomes(2));
+		assert_eq!(Stop::Watchpoint { from: 6, to: 2 }, debugger.resume());
+	}
+
+	#[test]
+	fn gives_up_on_endless_loops() {
+		let mut debugger = debugger("jmp 0").with_cycle_limit(6);
+		assert_eq!(Stop::CycleLimit, debugger.resume());
+		assert_eq!(6, debugger.cpu.cycle());
+	}
+
+	#[test]
+	fn shows_the_screen_so_far() {
+		let mut debugger = debugger(&"noop\n".repeat(50));
+		debugger.step_cycles(43);
+		let screen = debugger.screen();
+		let rows = screen.lines().collect::<Vec<&str>>();
+		assert_eq!(6, rows.len());
+		assert_eq!("#".repeat(3) + &".".repeat(37), rows[0]);
+		assert_eq!("###", &rows[1][..3]);
+		assert_eq!(" ".repeat(37), rows[1][3..]);
+		assert_eq!(" ".repeat(40), rows[5]);
+	}
+
+	#[test]
+	fn lists_around_the_program_counter() {
+		let mut debugger = debugger(&"noop\n".repeat(20));
+		debugger.add_breakpoint(Breakpoint::Instruction(10));
+		debugger.step_cycles(8);
+		let listing = debugger.listing();
+		assert_eq!(11, listing.lines().count());
+		assert_eq!(">     8  noop         line 9", listing.lines().nth(5).unwrap());
+		assert_eq!(" *   10  noop         line 11", listing.lines().nth(7).unwrap());
+	}
+
+	#[test]
+	fn parses_commands() {
+		assert_eq!(Ok(DebugCommand::Step(1)), parse_debug_command("s"));
+		assert_eq!(Ok(DebugCommand::Next(12)), parse_debug_command("next 12"));
+		assert_eq!(Ok(DebugCommand::Break(Breakpoint::Cycle(220))), parse_debug_command("b cycle 220"));
+		assert_eq!(Ok(DebugCommand::Delete(Breakpoint::Instruction(7))), parse_debug_command("delete pc 7"));
+		assert_eq!(Ok(DebugCommand::Watch(Watchpoint::Becomes(-3))), parse_debug_command("watch -3"));
+		assert_eq!(Err(DebugCommandError::MissingArgument), parse_debug_command("break cycle"));
+		assert_eq!(Err(DebugCommandError::BadArgument("soon".to_string())), parse_debug_command("step soon"));
+		assert_eq!(Err(DebugCommandError::UnknownCommand("jump".to_string())), parse_debug_command("jump 3"));
+	}
+
+	#[test]
+	fn reports_each_stop() {
+		let mut debugger = debugger("addx 3\nhalt");
+		assert_eq!("breakpoint at instruction 1", debugger.execute(&DebugCommand::Break(Breakpoint::Instruction(1))));
+		let output = debugger.execute(&DebugCommand::Continue);
+		assert!(output.starts_with("stopped at instruction 1\ncycle 2 done, pc 1 'halt' on line 2, x=4\n##"), "{}", output);
+		assert!(debugger.execute(&DebugCommand::Continue).starts_with("program finished\nfinished after 3 cycles with x=4\n"));
+	}
+}
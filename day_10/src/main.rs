@@ -1,132 +1,156 @@
+mod asm;
+mod cpu;
+mod crt;
+mod debugger;
+mod ocr;
+mod trace;
+
+use asm::{parse_program, Program};
+use cpu::{ClockListener, Cpu, Tick};
+use crt::Crt;
+use debugger::{parse_debug_command, Debugger};
+use ocr::{recognise, Font, OcrError};
+use trace::{TraceFormat, Tracer};
 use std::env;
 use std::fs;
-
-#[derive(Debug)]
-#[derive(PartialEq)]
-enum Instruction {
-    Add(i32),
-    Noop
-}
-
-enum State {
-    Idle,
-    Adding(i32)
-}
+use std::io::{self, BufRead};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let file_path = args[1].clone();
     let contents = fs::read_to_string(file_path).expect("Should have been able to read the file");
 
-    println!("Problem 1: {}", problem_1(&contents));
-    println!("Problem 2: \n\n{}\n\n", problem_2(&contents));
-}
-
-fn problem_1(input: &str,) -> i32 {
-    let mut signal_strength = 0;
-    let mut register_value = 1;
-
-    let mut program_counter = 0;
+    // day_10 <input> --debug [--max-cycles <n>], then commands on stdin
+    if args.iter().any(|a| a == "--debug") {
+        let mut debugger = Debugger::new(read_program(&contents));
+        if let Some(limit) = cycle_limit(&args) {
+            debugger = debugger.with_cycle_limit(limit);
+        }
+        run_debugger(&mut debugger);
+        return;
+    }
 
-    let mut instruction_iter = input.split('\n').map(|l| parse_instruction(l));
-    let mut cpu_state = State::Idle;
-    loop {
-        program_counter += 1;
+    // day_10 <input> --format
+    // like --trace, prints nothing else so the output can be redirected as is
+    if args.iter().any(|a| a == "--format") {
+        print!("{}", read_program(&contents));
+        return;
+    }
 
-        if program_counter == 20 || (program_counter > 20 && (program_counter - 20) % 40 == 0) {
-            signal_strength += program_counter * register_value;
+    // day_10 <input> --trace <csv|json> [--max-cycles <n>]
+    if let Some(format) = find_option(&args, "--trace") {
+        let format = TraceFormat::parse(format).unwrap_or_else(|| panic!("Unknown trace format: {}", format));
+        let program = read_program(&contents);
+        let mut tracer = Tracer::new(&program);
+        // the trace up to the cycle limit still helps to see why a program loops
+        let result = configured_cpu(&program, cycle_limit(&args)).run(&mut [&mut tracer]);
+        print!("{}", tracer.render(format));
+        if let Err(e) = result {
+            panic!("Could not run the program: {}", e);
         }
-
-        match cpu_state {
-            State::Idle => {
-                match instruction_iter.next() {
-                    None => {
-                        // program done
-                        break;
-                    },
-                    Some(Instruction::Noop) => {
-                        // do nothing
-                    },
-                    Some(Instruction::Add(val)) => {
-                        cpu_state = State::Adding(val);
-                    }
-                };
-            },
-            State::Adding(val) => {
-                // this is the second tick
-                register_value += val;
-                cpu_state = State::Idle;
-            }
-        };
+        return;
     }
 
-    signal_strength
-}
+    // both problems come from the same run, with [--max-cycles <n>] for programs that loop
+    let puzzle = run_puzzle(&contents, cycle_limit(&args));
+    println!("Problem 1: {}", puzzle.signal_strength);
+    match problem_2(&puzzle.screen) {
+        Ok(letters) => println!("Problem 2: {}", letters),
+        Err(e) => println!("Problem 2: could not read the screen\n{}\n\n{}\n", e, puzzle.screen)
+    }
 
-fn problem_2(input: &str) -> String {
-    let mut output_display = String::from("");
-    let mut register_value = 1;
+    // day_10 <input> --raw
+    if args.iter().any(|a| a == "--raw") {
+        println!("\n{}\n", puzzle.screen);
+    }
 
-    let mut program_counter = 0;
+    // day_10 <input> --registers [--max-cycles <n>]
+    if args.iter().any(|a| a == "--registers") {
+        let mut cpu = configured_cpu(&read_program(&contents), cycle_limit(&args));
+        let cycles = cpu.run(&mut []).unwrap_or_else(|e| panic!("Could not run the program: {}", e));
+        println!("Finished after {} cycles with {}", cycles, cpu.registers());
+    }
+}
 
-    let mut instruction_iter = input.split('\n').map(|l| parse_instruction(l)).peekable();
-    let mut cpu_state = State::Idle;
-    loop {
-        program_counter += 1;
+fn cycle_limit(args: &[String]) -> Option<u64> {
+    find_option(args, "--max-cycles").map(|l| l.parse().expect("Max cycles should be a number"))
+}
 
-        if instruction_iter.peek().is_none() {
-            // program done, exit
-            println!("program done");
+fn run_debugger(debugger: &mut Debugger) {
+    println!("{}", debugger.status());
+    for line in io::stdin().lock().lines() {
+        let line = line.expect("Should have been able to read stdin");
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line == "quit" || line == "q" {
             break;
         }
 
-        let crt_index_match = (program_counter - 1) % 40;
-        
-        if program_counter > 1 && crt_index_match == 0 {
-            output_display += "\n";
-        }
-        if crt_index_match >= std::cmp::max(0, register_value - 1) && crt_index_match <= std::cmp::max(0, register_value + 1) {
-            output_display += "#";
-        } else {
-            output_display += ".";
+        match parse_debug_command(line) {
+            Ok(command) => println!("{}", debugger.execute(&command)),
+            Err(e) => eprintln!("{}", e)
         }
+    }
+}
 
-        match cpu_state {
-            State::Idle => {
-                match instruction_iter.next() {
-                    None => {
-                        unreachable!();
-                    },
-                    Some(Instruction::Noop) => {
-                        // do nothing
-                    },
-                    Some(Instruction::Add(val)) => {
-                        cpu_state = State::Adding(val);
-                    }
-                };
-            },
-            State::Adding(val) => {
-                // this is the second tick
-                register_value += val;
-                cpu_state = State::Idle;
-            }
-        };
+fn find_option<'a>(args: &'a [String], name: &str) -> Option<&'a String> {
+    args.iter().position(|a| a == name).and_then(|i| args.get(i + 1))
+}
+
+// Adds up cycle * x on cycle 20 and every 40 cycles after.
+struct SignalStrength {
+    total: i64
+}
+
+impl ClockListener for SignalStrength {
+    fn on_tick(&mut self, tick: &Tick) {
+        if tick.cycle >= 20 && (tick.cycle - 20).is_multiple_of(40) {
+            let cycle = i64::try_from(tick.cycle).unwrap_or(i64::MAX);
+            self.total = self.total.saturating_add(cycle.saturating_mul(tick.registers.x().into()));
+        }
     }
+}
+
+struct Puzzle {
+    signal_strength: i64,
+    screen: String
+}
+
+// Runs the program once, with the signal strength sampler and the CRT both
+// listening to the same clock.
+fn run_puzzle(input: &str, cycle_limit: Option<u64>) -> Puzzle {
+    let mut signal_strength = SignalStrength { total: 0 };
+    let mut crt = Crt::new();
+    configured_cpu(&read_program(input), cycle_limit)
+        .run(&mut [&mut signal_strength, &mut crt])
+        .unwrap_or_else(|e| panic!("Could not run the program: {}", e));
+
+    Puzzle { signal_strength: signal_strength.total, screen: crt.render() }
+}
 
-    output_display
+fn problem_2(screen: &str) -> Result<String, OcrError> {
+    recognise(screen, &Font::aoc_4x6())
 }
 
-fn parse_instruction(line: &str) -> Instruction {
-    if line.starts_with("noop") {
-        return Instruction::Noop;
-    } else {
-        return Instruction::Add(line.split(' ').last().unwrap().parse::<i32>().unwrap());
+fn configured_cpu(program: &Program, cycle_limit: Option<u64>) -> Cpu {
+    let cpu = Cpu::new(program.instructions());
+    match cycle_limit {
+        Some(limit) => cpu.with_cycle_limit(limit),
+        None => cpu
     }
 }
 
+fn read_program(input: &str) -> Program {
+    parse_program(input).unwrap_or_else(|e| panic!("Could not read the program: {}", e))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use asm::{parse_instruction, AsmErrorKind};
+    use cpu::{Condition, Instruction, Operand, Register};
     #[test]
     fn first() {
         let input = "\
@@ -276,14 +300,18 @@ addx -11
 noop
 noop
 noop";
-        assert_eq!(13140, problem_1(&input));
+        assert_eq!(13140, run_puzzle(input, None).signal_strength);
     }
 
     #[test]
     fn parses() {
-        assert_eq!(Instruction::Noop, parse_instruction("noop"));
-        assert_eq!(Instruction::Add(1), parse_instruction("addx 1"));
-        assert_eq!(Instruction::Add(-1), parse_instruction("addx -1"));
+        assert_eq!(Ok(Instruction::Noop), parse_instruction("noop"));
+        assert_eq!(Ok(Instruction::Add { register: Register::X, operand: Operand::Value(1) }), parse_instruction("addx 1"));
+        assert_eq!(Ok(Instruction::Add { register: Register::X, operand: Operand::Value(-1) }), parse_instruction("addx -1"));
+        assert_eq!(Ok(Instruction::Mul { register: Register::X, operand: Operand::Register(Register::parse("a").unwrap()) }), parse_instruction("mulx a"));
+        assert_eq!(Ok(Instruction::Branch { condition: Condition::NotZero, register: Register::X, offset: -3 }), parse_instruction("jnz x -3"));
+        assert_eq!(Ok(Instruction::Jump(2)), parse_instruction("jmp 2"));
+        assert_eq!(Ok(Instruction::Halt), parse_instruction("halt"));
     }
 
     #[test]
@@ -436,12 +464,42 @@ noop
 noop
 noop";
         let target = "##..##..##..##..##..##..##..##..##..##..\n###...###...###...###...###...###...###.\n####....####....####....####....####....\n#####.....#####.....#####.....#####.....\n######......######......######......####\n#######.......#######.......#######.....";
-        let res = problem_2(&input);
+        let res = run_puzzle(input, None).screen;
         for (i, tc) in res.chars().enumerate() {
             if target.chars().nth(i).unwrap() != tc {
                 panic!("Gross");
             }
         }
-        assert_eq!(target, &problem_2(&input));
+        assert_eq!(target, &res);
+        // the example is a test pattern rather than letters
+        assert!(matches!(problem_2(&res), Err(OcrError::UnknownGlyphs(_))));
+    }
+
+    #[test]
+    fn rejects_unknown_instructions() {
+        let error = parse_instruction("subx 3").unwrap_err();
+        assert_eq!(AsmErrorKind::UnknownOpcode("subx".to_string()), error.kind);
+        assert_eq!("line 1 column 1 'subx 3': unknown instruction subx", error.to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "program did not finish within 5 cycles")]
+    fn stops_endless_programs_at_the_cycle_limit() {
+        run_puzzle("jmp 0", Some(5));
+    }
+
+    #[test]
+    fn signal_strength_does_not_overflow() {
+        // x is doubled to 2^30 by cycle 91, long before the 100th sample
+        let input = format!("{}{}", "mulx 2\n".repeat(30), "noop\n".repeat(4000));
+        assert!(run_puzzle(&input, None).signal_strength > i64::from(i32::MAX));
+    }
+
+    #[test]
+    fn runs_loops_and_halts() {
+        // each pass round the loop takes 13 cycles, and cycle 20 comes after x
+        // has doubled twice; the halt stops the program before cycle 60
+        let input = "seta 3\nmulx 2\naddx 0\naddx 0\naddx 0\nadda -1\njnz a -5\nhalt\naddx 100\naddx 100\naddx 100";
+        assert_eq!(4 * 20, run_puzzle(input, None).signal_strength);
     }
 }
\ No newline at end of file
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::fmt;
+
+// The capital letters Advent of Code draws on 4x6 screens, with one blank
+// column between letters. Not every letter has turned up in a puzzle yet.
+const AOC_4X6: [(char, [&str; 6]); 18] = [
+	('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+	('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+	('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+	('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+	('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+	('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+	('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+	('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+	('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+	('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+	('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+	('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+	('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+	('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+	('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+	('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+	('Y', ["#...", "#...", ".#.#", "..#.", "..#.", "..#."]),
+	('Z', ["####", "...#", "..#.", ".#..", "#...", "####"])
+];
+
+// Letters of one size, each followed by `spacing` columns that are skipped.
+pub struct Font {
+	width: usize,
+	height: usize,
+	spacing: usize,
+	glyphs: HashMap<Vec<String>, char>
+}
+
+impl Font {
+	pub fn new(width: usize, height: usize, spacing: usize) -> Font {
+		assert!(width > 0 && height > 0, "glyphs need at least one pixel");
+		Font { width, height, spacing, glyphs: HashMap::new() }
+	}
+
+	pub fn aoc_4x6() -> Font {
+		AOC_4X6.iter().fold(Font::new(4, 6, 1), |font, (letter, rows)| font.with_glyph(*letter, rows))
+	}
+
+	// Rows are drawn with '#' for lit pixels and '.' for dark ones.
+	pub fn with_glyph(mut self, letter: char, rows: &[&str]) -> Font {
+		assert!(rows.len() == self.height && rows.iter().all(|r| r.chars().count() == self.width), "{} does not fit a {}x{} font", letter, self.width, self.height);
+		self.glyphs.insert(rows.iter().map(|r| r.to_string()).collect(), letter);
+		self
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub struct UnknownGlyph {
+	// of the glyph's leftmost pixel, counted from 0
+	pub column: usize,
+	pub rows: Vec<String>
+}
+
+#[derive(Debug, PartialEq)]
+pub enum OcrError {
+	WrongHeight { expected: usize, found: usize },
+	UnknownGlyphs(Vec<UnknownGlyph>)
+}
+
+impl fmt::Display for OcrError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			OcrError::WrongHeight { expected, found } => write!(f, "the font is {} rows tall but the picture has {}", expected, found),
+			OcrError::UnknownGlyphs(glyphs) => {
+				for (i, glyph) in glyphs.iter().enumerate() {
+					if i > 0 {
+						writeln!(f)?;
+					}
+					write!(f, "unknown glyph at column {}:\n{}", glyph.column, glyph.rows.join("\n"))?;
+				}
+				Ok(())
+			}
+		}
+	}
+}
+
+// Reads a '#'/'.' picture one glyph at a time. Blank glyphs read as spaces,
+// and a picture whose width is not a whole number of glyphs is padded with
+// dark pixels.
+pub fn recognise(picture: &str, font: &Font) -> Result<String, OcrError> {
+	let lines = picture.lines().map(|l| l.chars().collect::<Vec<char>>()).collect::<Vec<Vec<char>>>();
+	if lines.len() != font.height {
+		return Err(OcrError::WrongHeight { expected: font.height, found: lines.len() });
+	}
+
+	let width = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+	let stride = font.width + font.spacing;
+	let mut text = String::new();
+	let mut unknown = vec![];
+	for column in (0..width).step_by(stride) {
+		let rows = lines.iter()
+			.map(|line| (column..column + font.width).map(|x| line.get(x).copied().unwrap_or('.')).collect::<String>())
+			.collect::<Vec<String>>();
+
+		match font.glyphs.get(&rows) {
+			Some(letter) => text.push(*letter),
+			None if rows.iter().all(|r| r.chars().all(|c| c == '.')) => text.push(' '),
+			None => {
+				text.push('?');
+				unknown.push(UnknownGlyph { column, rows });
+			}
+		}
+	}
+
+	if unknown.is_empty() {
+		Ok(text)
+	} else {
+		Err(OcrError::UnknownGlyphs(unknown))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn picture(letters: &[[&str; 6]]) -> String {
+		(0..6).map(|row| letters.iter().map(|l| format!("{}.", l[row])).collect::<String>()).collect::<Vec<String>>().join("\n")
+	}
+
+	#[test]
+	fn reads_every_letter_in_the_font() {
+		let letters = AOC_4X6.iter().map(|(_, rows)| *rows).collect::<Vec<[&str; 6]>>();
+		assert_eq!("ABCEFGHIJKLOPRSUYZ", recognise(&picture(&letters), &Font::aoc_4x6()).unwrap());
+	}
+
+	#[test]
+	fn reads_a_screen() {
+		let screen = "\
+####.####.###..####.#..#..##..#..#.###..
+...#.#....#..#.#....#..#.#..#.#..#.#..#.
+..#..###..###..###..####.#....#..#.#..#.
+.#...#....#..#.#....#..#.#.##.#..#.###..
+#....#....#..#.#....#..#.#..#.#..#.#....
+####.#....###..#....#..#..###..##..#....";
+		assert_eq!("ZFBFHGUP", recognise(screen, &Font::aoc_4x6()).unwrap());
+	}
+
+	#[test]
+	fn reports_unknown_glyphs_by_column() {
+		let smudge = ["#..#", ".##.", ".##.", ".##.", ".##.", "#..#"];
+		let blank = ["....", "....", "....", "....", "....", "...."];
+		let screen = picture(&[AOC_4X6[0].1, blank, smudge]);
+		let error = recognise(&screen, &Font::aoc_4x6()).unwrap_err();
+		assert_eq!(OcrError::UnknownGlyphs(vec![UnknownGlyph { column: 10, rows: smudge.iter().map(|r| r.to_string()).collect() }]), error);
+		assert!(error.to_string().starts_with("unknown glyph at column 10:\n#..#\n.##."));
+		assert_eq!(OcrError::WrongHeight { expected: 6, found: 2 }, recognise("#\n#", &Font::aoc_4x6()).unwrap_err());
+	}
+
+	#[test]
+	fn takes_other_fonts() {
+		let font = Font::new(2, 2, 0).with_glyph('/', &[".#", "#."]).with_glyph('\\', &["#.", ".#"]);
+		assert_eq!("/\\/", recognise(".##..#\n#..##.", &font).unwrap());
+	}
+}
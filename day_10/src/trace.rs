@@ -0,0 +1,144 @@
+use crate::asm::Program;
+use crate::cpu::{ClockListener, Instruction, Tick};
+use crate::crt::{column, sprite_covers};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TraceFormat {
+	Csv,
+	Json
+}
+
+impl TraceFormat {
+	pub fn parse(name: &str) -> Option<TraceFormat> {
+		match name {
+			"csv" => Some(TraceFormat::Csv),
+			"json" => Some(TraceFormat::Json),
+			_ => None
+		}
+	}
+}
+
+// One clock cycle, as the CPU and the CRT saw it.
+#[derive(Debug, PartialEq)]
+pub struct TraceRow {
+	pub cycle: u64,
+	pub pc: usize,
+	// of the instruction in the source
+	pub line: usize,
+	pub instruction: Instruction,
+	pub x: i32,
+	pub column: usize,
+	pub pixel: bool
+}
+
+impl TraceRow {
+	// The leftmost and rightmost columns the sprite covers.
+	pub fn sprite(&self) -> (i32, i32) {
+		(self.x - 1, self.x + 1)
+	}
+}
+
+pub struct Tracer<'a> {
+	program: &'a Program,
+	rows: Vec<TraceRow>
+}
+
+impl<'a> Tracer<'a> {
+	pub fn new(program: &'a Program) -> Tracer<'a> {
+		Tracer { program, rows: vec![] }
+	}
+
+	pub fn render(&self, format: TraceFormat) -> String {
+		match format {
+			TraceFormat::Csv => to_csv(&self.rows),
+			TraceFormat::Json => to_json(&self.rows)
+		}
+	}
+}
+
+impl ClockListener for Tracer<'_> {
+	fn on_tick(&mut self, tick: &Tick) {
+		let x = tick.registers.x();
+		let column = column(tick.cycle);
+		self.rows.push(TraceRow {
+			cycle: tick.cycle,
+			pc: tick.pc,
+			line: self.program.statements[tick.pc].position.line,
+			instruction: tick.instruction,
+			x,
+			column,
+			pixel: sprite_covers(column, x)
+		});
+	}
+}
+
+fn pixel(lit: bool) -> char {
+	if lit { '#' } else { '.' }
+}
+
+pub fn to_csv(rows: &[TraceRow]) -> String {
+	let mut csv = String::from("cycle,pc,line,instruction,x,sprite_left,sprite_right,column,pixel\n");
+	for row in rows {
+		let (left, right) = row.sprite();
+		csv.push_str(&format!("{},{},{},{},{},{},{},{},{}\n", row.cycle, row.pc, row.line, row.instruction, row.x, left, right, row.column, pixel(row.pixel)));
+	}
+
+	csv
+}
+
+// An array with one object per line. Instructions never hold characters that
+// need escaping.
+pub fn to_json(rows: &[TraceRow]) -> String {
+	let objects = rows.iter()
+		.map(|row| {
+			let (left, right) = row.sprite();
+			format!(
+				"  {{\"cycle\": {}, \"pc\": {}, \"line\": {}, \"instruction\": \"{}\", \"x\": {}, \"sprite\": [{}, {}], \"column\": {}, \"pixel\": \"{}\"}}",
+				row.cycle, row.pc, row.line, row.instruction, row.x, left, right, row.column, pixel(row.pixel)
+			)
+		})
+		.collect::<Vec<String>>();
+
+	if objects.is_empty() {
+		"[]\n".to_string()
+	} else {
+		format!("[\n{}\n]\n", objects.join(",\n"))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::asm::parse_program;
+	use crate::cpu::Cpu;
+
+	fn trace(source: &str, format: TraceFormat) -> String {
+		let program = parse_program(source).unwrap();
+		let mut tracer = Tracer::new(&program);
+		Cpu::new(program.instructions()).run(&mut [&mut tracer]).unwrap();
+		tracer.render(format)
+	}
+
+	#[test]
+	fn traces_as_csv() {
+		assert_eq!("\
+cycle,pc,line,instruction,x,sprite_left,sprite_right,column,pixel
+1,0,1,noop,1,0,2,0,#
+2,1,3,addx 3,1,0,2,1,#
+3,1,3,addx 3,1,0,2,2,#
+4,2,4,addx -5,4,3,5,3,#
+5,2,4,addx -5,4,3,5,4,#
+", trace("noop\n\naddx 3\naddx -5", TraceFormat::Csv));
+	}
+
+	#[test]
+	fn traces_as_json() {
+		assert_eq!("\
+[
+  {\"cycle\": 1, \"pc\": 0, \"line\": 1, \"instruction\": \"setx -4\", \"x\": 1, \"sprite\": [0, 2], \"column\": 0, \"pixel\": \"#\"},
+  {\"cycle\": 2, \"pc\": 1, \"line\": 2, \"instruction\": \"halt\", \"x\": -4, \"sprite\": [-5, -3], \"column\": 1, \"pixel\": \".\"}
+]
+", trace("setx -4\nhalt", TraceFormat::Json));
+		assert_eq!("[]\n", trace("", TraceFormat::Json));
+	}
+}